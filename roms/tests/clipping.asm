@@ -0,0 +1,56 @@
+; Sprite clipping test.
+;
+; Each test stores its VF result in VA..VC, which are then drawn from left to right.
+; Expected with `clip_sprites`: 0 0 1
+; Expected without:             1 1 1
+
+    LD I, row
+
+; 1. A sprite crossing the right edge does not wrap to the left side.
+    LD V0, 60
+    LD V1, 0
+    DRW V0, V1, 1
+    LD I, pixel
+    LD V0, 0
+    DRW V0, V1, 1
+    LD VA, VF
+
+; 2. A sprite crossing the bottom edge does not wrap to the top.
+    LD I, column
+    LD V0, 10
+    LD V1, 31
+    DRW V0, V1, 2
+    LD I, pixel
+    LD V1, 0
+    DRW V0, V1, 1
+    LD VB, VF
+
+; 3. The starting coordinate always wraps: (69, 2) is drawn at (5, 2).
+    LD V0, 69
+    LD V1, 2
+    DRW V0, V1, 1
+    LD V0, 5
+    DRW V0, V1, 1
+    LD VC, VF
+
+    CLS
+    LD V0, 4
+    LD V1, 4
+    LD F, VA
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VB
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VC
+    DRW V0, V1, 5
+
+end:
+    JP end
+
+pixel:
+    DB 0x80
+column:
+    DB 0x80 0x80
+row:
+    DB 0xFF
@@ -0,0 +1,70 @@
+; Sprite collision (VF) test.
+;
+; Each test stores its VF result in VA..VE, which are then drawn from left to right.
+; Expected: 1 1 0 0 1
+
+    LD I, pixel
+    LD V0, 0
+    LD V1, 0
+
+; 1. Drawing over a lit pixel sets VF.
+    DRW V0, V1, 1
+    DRW V0, V1, 1
+    LD VA, VF
+
+; 2. A collision in the first row is not cleared by pixels turned on in the second one.
+    DRW V0, V1, 1
+    LD I, column
+    DRW V0, V1, 2
+    LD VB, VF
+
+; 3. VF is reset when the sprite does not draw anything.
+    LD I, empty
+    LD VF, 1
+    DRW V0, V1, 1
+    LD VC, VF
+
+; 4. No collision on an empty part of the screen.
+    LD I, pixel
+    LD V0, 20
+    LD V1, 10
+    DRW V0, V1, 1
+    LD VD, VF
+
+; 5. A collision on the first pixel of a row is not cleared by the pixels after it.
+    LD V0, 0
+    LD V1, 5
+    DRW V0, V1, 1
+    LD I, row
+    DRW V0, V1, 1
+    LD VE, VF
+
+    CLS
+    LD V0, 4
+    LD V1, 4
+    LD F, VA
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VB
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VC
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VD
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VE
+    DRW V0, V1, 5
+
+end:
+    JP end
+
+pixel:
+    DB 0x80
+column:
+    DB 0x80 0x80
+empty:
+    DB 0x00
+row:
+    DB 0xFF
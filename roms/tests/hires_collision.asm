@@ -0,0 +1,61 @@
+; SUPER-CHIP hi-res collision test.
+;
+; Each test stores its VF result in VA..VD, which are then drawn from left to right.
+; Expected with `count_collided_rows`: 2 2 4 1
+; Expected without:                    1 0 1 1
+
+    HIGH
+
+; 1. Three rows over the bottom of a 16x16 sprite: two of them collide.
+    LD I, square
+    LD V0, 0
+    LD V1, 0
+    DRW V0, V1, 0
+    LD I, column
+    LD V1, 14
+    DRW V0, V1, 3
+    LD VA, VF
+
+; 2. Rows clipped at the bottom of the screen are counted.
+    LD V0, 100
+    LD V1, 62
+    DRW V0, V1, 4
+    LD VB, VF
+
+; 3. Collided and clipped rows add up.
+    DRW V0, V1, 4
+    LD VC, VF
+
+; 4. In lo-res mode VF is a flag again.
+    LOW
+    LD V0, 0
+    LD V1, 0
+    DRW V0, V1, 2
+    DRW V0, V1, 2
+    LD VD, VF
+
+    CLS
+    LD V0, 4
+    LD V1, 4
+    LD F, VA
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VB
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VC
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VD
+    DRW V0, V1, 5
+
+end:
+    JP end
+
+column:
+    DB 0x80 0x80 0x80 0x80
+square:
+    DB 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF
+    DB 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF
+    DB 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF
+    DB 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF 0xFF
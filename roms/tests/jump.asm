@@ -0,0 +1,60 @@
+; Jump with offset (BNNN) test.
+;
+; Each test jumps into a table of jumps with `JP V0, table`, and stores the entry it landed on in VA..VB,
+; which are then drawn from left to right. 0 means that it didn't jump.
+; The table is at 0x2XX, so with `jump_with_vx` the offset is V2 instead of V0.
+; Expected without `jump_with_vx`: 1 2
+; Expected with:                   2 1
+
+; 1. V0 = 2 (entry 1), V2 = 4 (entry 2).
+    LD V0, 2
+    LD V2, 4
+    LD V3, 0
+    JP V0, table
+    LD VA, 0
+    JP second
+
+; 2. V0 = 4 (entry 2), V2 = 2 (entry 1).
+second:
+    LD V0, 4
+    LD V2, 2
+    LD V3, 1
+    JP V0, table
+    LD VB, 0
+
+show:
+    CLS
+    LD V0, 4
+    LD V1, 4
+    LD F, VA
+    DRW V0, V1, 5
+    ADD V0, 6
+    LD F, VB
+    DRW V0, V1, 5
+
+end:
+    JP end
+
+table:
+    JP entry0
+    JP entry1
+    JP entry2
+
+entry0:
+    LD V4, 0
+    JP found
+entry1:
+    LD V4, 1
+    JP found
+entry2:
+    LD V4, 2
+
+; V3 tells which test jumped here.
+found:
+    SE V3, 0
+    JP found_second
+    LD VA, V4
+    JP second
+found_second:
+    LD VB, V4
+    JP show
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+/// An operand of an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operand {
+    V(u16),
+    I,
+    /// `[I]`, the memory at I.
+    AtI,
+    DelayTimer,
+    SoundTimer,
+    Key,
+    Font,
+    HiresFont,
+    Bcd,
+    /// The HP-48 flags registers.
+    Flags,
+    /// A number or a label.
+    Value(u16),
+}
+
+/// A line of source, split into its parts.
+struct Line<'a> {
+    number: usize,
+    label: Option<&'a str>,
+    mnemonic: Option<String>,
+    operands: Vec<&'a str>,
+}
+
+/// Assembles a program written with the mnemonics of Cowgod's Chip-8 Technical Reference, to be loaded at `load_address`.
+///
+/// Each line holds a `label:`, an instruction (`LD V0, #1F`), or both. Comments start with `;`.
+/// Numbers are decimal, or hex with `#`, `$` or `0x`, or binary with `%` or `0b`.
+/// Operands are separated by commas or spaces. `DB` and `DW` insert bytes and 16-bit words.
+pub fn assemble(source: &str, load_address: u16) -> Result<Vec<u8>, String> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| parse_line(index + 1, text))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // First pass: find the address of every label
+    let mut labels = HashMap::new();
+    let mut address = load_address as usize;
+
+    for line in &lines {
+        if let Some(label) = line.label {
+            if labels.insert(label.to_string(), address as u16).is_some() {
+                return Err(format!(
+                    "line {}: the label '{label}' is defined twice",
+                    line.number
+                ));
+            }
+        }
+
+        address += match line.mnemonic.as_deref() {
+            Some("DB") => line.operands.len(),
+            Some("DW") => line.operands.len() * 2,
+            Some(_) => 2,
+            None => 0,
+        };
+    }
+
+    if address > 0x1000 {
+        return Err(format!(
+            "the program ends at {address:#05X}, past the end of the memory"
+        ));
+    }
+
+    // Second pass: encode the instructions
+    let mut program = Vec::new();
+
+    for line in &lines {
+        if let Some(mnemonic) = &line.mnemonic {
+            encode(mnemonic, &line.operands, &labels, &mut program)
+                .map_err(|error| format!("line {}: {error}", line.number))?;
+        }
+    }
+
+    Ok(program)
+}
+
+fn parse_line(number: usize, text: &str) -> Result<Line<'_>, String> {
+    let mut text = text.split(';').next().unwrap_or("").trim();
+    let mut label = None;
+
+    if let Some((name, rest)) = text.split_once(':') {
+        let name = name.trim();
+
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("line {number}: invalid label '{name}'"));
+        }
+
+        label = Some(name);
+        text = rest.trim();
+    }
+
+    if text.is_empty() {
+        return Ok(Line {
+            number,
+            label,
+            mnemonic: None,
+            operands: Vec::new(),
+        });
+    }
+
+    let (mnemonic, operands) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let operands = operands
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|operand| !operand.is_empty())
+        .collect();
+
+    Ok(Line {
+        number,
+        label,
+        mnemonic: Some(mnemonic.to_ascii_uppercase()),
+        operands,
+    })
+}
+
+fn parse_number(text: &str) -> Option<u32> {
+    let (digits, radix) =
+        if let Some(hex) = text.strip_prefix('#').or_else(|| text.strip_prefix('$')) {
+            (hex, 16)
+        } else if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            (hex, 16)
+        } else if let Some(binary) = text.strip_prefix('%').or_else(|| text.strip_prefix("0b")) {
+            (binary, 2)
+        } else {
+            (text, 10)
+        };
+
+    u32::from_str_radix(digits, radix).ok()
+}
+
+fn parse_operand(text: &str, labels: &HashMap<String, u16>) -> Result<Operand, String> {
+    let upper = text.to_ascii_uppercase();
+
+    let operand = match upper.as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::AtI,
+        "DT" => Operand::DelayTimer,
+        "ST" => Operand::SoundTimer,
+        "K" => Operand::Key,
+        "F" => Operand::Font,
+        "HF" => Operand::HiresFont,
+        "B" => Operand::Bcd,
+        "R" => Operand::Flags,
+        _ if upper.len() == 2 && upper.starts_with('V') => {
+            match u16::from_str_radix(&upper[1..], 16) {
+                Ok(register) => Operand::V(register),
+                Err(_) => return Err(format!("invalid register '{text}'")),
+            }
+        }
+        _ => match (parse_number(text), labels.get(text)) {
+            (Some(value), _) if value <= 0xFFFF => Operand::Value(value as u16),
+            (Some(_), _) => return Err(format!("'{text}' is too large")),
+            (None, Some(address)) => Operand::Value(*address),
+            (None, None) => return Err(format!("unknown label '{text}'")),
+        },
+    };
+
+    Ok(operand)
+}
+
+/// Checks that a value fits in the bits of its field.
+fn fits(value: u16, max: u16) -> Result<u16, String> {
+    if value <= max {
+        Ok(value)
+    } else {
+        Err(format!("#{value:X} doesn't fit, the maximum is #{max:X}"))
+    }
+}
+
+fn encode(
+    mnemonic: &str,
+    operands: &[&str],
+    labels: &HashMap<String, u16>,
+    program: &mut Vec<u8>,
+) -> Result<(), String> {
+    let operands = operands
+        .iter()
+        .map(|operand| parse_operand(operand, labels))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    use Operand::*;
+
+    let opcode = match (mnemonic, operands.as_slice()) {
+        ("DB", values) => {
+            for value in values {
+                match value {
+                    Value(byte) => program.push(fits(*byte, 0xFF)? as u8),
+                    _ => return Err(String::from("DB expects numbers")),
+                }
+            }
+
+            return Ok(());
+        }
+        ("DW", values) => {
+            for value in values {
+                match value {
+                    Value(word) => program.extend_from_slice(&word.to_be_bytes()),
+                    _ => return Err(String::from("DW expects numbers")),
+                }
+            }
+
+            return Ok(());
+        }
+
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("SCR", []) => 0x00FB,
+        ("SCL", []) => 0x00FC,
+        ("EXIT", []) => 0x00FD,
+        ("LOW", []) => 0x00FE,
+        ("HIGH", []) => 0x00FF,
+        ("SCD", [Value(n)]) => 0x00C0 | fits(*n, 0xF)?,
+        ("SYS", [Value(address)]) => fits(*address, 0xFFF)?,
+        ("JP", [Value(address)]) => 0x1000 | fits(*address, 0xFFF)?,
+        ("JP", [V(0), Value(address)]) => 0xB000 | fits(*address, 0xFFF)?,
+        ("CALL", [Value(address)]) => 0x2000 | fits(*address, 0xFFF)?,
+
+        ("SE", [V(x), Value(byte)]) => 0x3000 | x << 8 | fits(*byte, 0xFF)?,
+        ("SNE", [V(x), Value(byte)]) => 0x4000 | x << 8 | fits(*byte, 0xFF)?,
+        ("SE", [V(x), V(y)]) => 0x5000 | x << 8 | y << 4,
+        ("SNE", [V(x), V(y)]) => 0x9000 | x << 8 | y << 4,
+
+        ("LD", [V(x), Value(byte)]) => 0x6000 | x << 8 | fits(*byte, 0xFF)?,
+        ("ADD", [V(x), Value(byte)]) => 0x7000 | x << 8 | fits(*byte, 0xFF)?,
+        ("LD", [V(x), V(y)]) => 0x8000 | x << 8 | y << 4,
+        ("OR", [V(x), V(y)]) => 0x8001 | x << 8 | y << 4,
+        ("AND", [V(x), V(y)]) => 0x8002 | x << 8 | y << 4,
+        ("XOR", [V(x), V(y)]) => 0x8003 | x << 8 | y << 4,
+        ("ADD", [V(x), V(y)]) => 0x8004 | x << 8 | y << 4,
+        ("SUB", [V(x), V(y)]) => 0x8005 | x << 8 | y << 4,
+        ("SHR", [V(x)]) => 0x8006 | x << 8,
+        ("SHR", [V(x), V(y)]) => 0x8006 | x << 8 | y << 4,
+        ("SUBN", [V(x), V(y)]) => 0x8007 | x << 8 | y << 4,
+        ("SHL", [V(x)]) => 0x800E | x << 8,
+        ("SHL", [V(x), V(y)]) => 0x800E | x << 8 | y << 4,
+
+        ("LD", [I, Value(address)]) => 0xA000 | fits(*address, 0xFFF)?,
+        ("RND", [V(x), Value(byte)]) => 0xC000 | x << 8 | fits(*byte, 0xFF)?,
+        ("DRW", [V(x), V(y), Value(n)]) => 0xD000 | x << 8 | y << 4 | fits(*n, 0xF)?,
+        ("SKP", [V(x)]) => 0xE09E | x << 8,
+        ("SKNP", [V(x)]) => 0xE0A1 | x << 8,
+
+        ("LD", [V(x), DelayTimer]) => 0xF007 | x << 8,
+        ("LD", [V(x), Key]) => 0xF00A | x << 8,
+        ("LD", [DelayTimer, V(x)]) => 0xF015 | x << 8,
+        ("LD", [SoundTimer, V(x)]) => 0xF018 | x << 8,
+        ("ADD", [I, V(x)]) => 0xF01E | x << 8,
+        ("LD", [Font, V(x)]) => 0xF029 | x << 8,
+        ("LD", [HiresFont, V(x)]) => 0xF030 | x << 8,
+        ("LD", [Bcd, V(x)]) => 0xF033 | x << 8,
+        ("LD", [AtI, V(x)]) => 0xF055 | x << 8,
+        ("LD", [V(x), AtI]) => 0xF065 | x << 8,
+        ("LD", [Flags, V(x)]) => 0xF075 | x << 8,
+        ("LD", [V(x), Flags]) => 0xF085 | x << 8,
+
+        _ => {
+            return Err(format!(
+                "unknown instruction '{mnemonic}' with these operands"
+            ))
+        }
+    };
+
+    program.extend_from_slice(&opcode.to_be_bytes());
+    Ok(())
+}
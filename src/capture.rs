@@ -0,0 +1,273 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{filter::ScreenFilter, palette::Palette, render::Viewport, CHIP8_HEIGHT, CHIP8_WIDTH};
+
+/// The size of a capture at `scale` pixels per CHIP-8 pixel. A hi-res screen is drawn at half the scale, so all the captures
+/// of a recording have the same size.
+pub fn size(scale: usize) -> (usize, usize) {
+    (CHIP8_WIDTH * scale.max(1), CHIP8_HEIGHT * scale.max(1))
+}
+
+/// The filtered screen scaled to `size(scale)`: one intensity per pixel, 0 for the background and 255 for the foreground.
+pub fn render(
+    screen: &ScreenFilter,
+    (screen_width, screen_height): (usize, usize),
+    scale: usize,
+) -> Vec<u8> {
+    let (width, height) = size(scale);
+    let viewport = Viewport::fit(width, height, screen_width, screen_height);
+
+    let mut image = vec![0; width * height];
+
+    for (y, row) in image.chunks_exact_mut(width).enumerate() {
+        let screen_y = match y.checked_sub(viewport.y) {
+            Some(y) if y < viewport.height => y / viewport.scale,
+            _ => continue,
+        };
+
+        for (x, pixel) in row.iter_mut().enumerate() {
+            if let Some(screen_x) = x.checked_sub(viewport.x).filter(|x| *x < viewport.width) {
+                *pixel = screen.intensity(screen_x / viewport.scale, screen_y);
+            }
+        }
+    }
+
+    image
+}
+
+/// The colors of the 256 intensities, as RGB triplets for the palette of a PNG or a GIF.
+fn color_table(palette: &Palette) -> Vec<u8> {
+    (0..=255)
+        .flat_map(|intensity| {
+            let [r, g, b, _] = palette.color(intensity);
+            [r, g, b]
+        })
+        .collect()
+}
+
+/// Writes an image from `render` as a PNG, in the colors of `palette`.
+pub fn save_png(path: &Path, image: &[u8], scale: usize, palette: &Palette) -> Result<(), String> {
+    let (width, height) = size(scale);
+    let error =
+        |error: &dyn std::fmt::Display| format!("Could not write {}: {error}", path.display());
+
+    let file = File::create(path).map_err(|e| error(&e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(color_table(palette));
+
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    writer.write_image_data(image).map_err(|e| error(&e))?;
+    writer.finish().map_err(|e| error(&e))
+}
+
+/// The first `rusty8-NNN.extension` file that doesn't exist in `directory`.
+pub fn next_file(directory: &Path, extension: &str) -> PathBuf {
+    (1..)
+        .map(|number| directory.join(format!("rusty8-{number:03}.{extension}")))
+        .find(|path| !path.exists())
+        .expect("Ran out of file names")
+}
+
+/// The shortest delay between the frames of a GIF that browsers respect, in hundredths of a second.
+const MIN_GIF_DELAY: u32 = 2;
+
+/// The delay of a GIF frame ending after `frames` 60Hz frames, when the previous ones add up to `hundredths`.
+/// The end is rounded, so that the delays don't drift.
+fn gif_delay(frames: u32, hundredths: u32) -> u32 {
+    ((frames * 100 + 30) / 60).saturating_sub(hundredths)
+}
+
+enum Output {
+    /// An animated GIF. The last frame is held back until it changes, to show it longer instead of repeating it.
+    /// Browsers slow down frames shorter than 2/100s, so those are replaced by the next one.
+    Gif {
+        encoder: gif::Encoder<BufWriter<File>>,
+        pending: Option<(Vec<u8>, u32)>,
+        /// The time written so far, in 60Hz frames and in the hundredths of a second of GIF delays.
+        frames: u32,
+        hundredths: u32,
+    },
+    /// RGBA pixels, one frame after the other, e.g. for `ffmpeg -f rawvideo -pix_fmt rgba -s WxH -r 60 -i file`.
+    Raw {
+        writer: BufWriter<File>,
+        palette: Palette,
+    },
+}
+
+/// Records every frame of the screen to a file, at a fixed scale and in the colors of a palette.
+pub struct Recorder {
+    path: PathBuf,
+    scale: usize,
+    output: Output,
+    frames: u32,
+}
+
+impl Recorder {
+    /// Starts recording to `path`: an animated GIF if it ends with `.gif`, raw RGBA frames otherwise.
+    pub fn create(path: &Path, scale: usize, palette: &Palette) -> Result<Self, String> {
+        let error =
+            |error: &dyn std::fmt::Display| format!("Could not write {}: {error}", path.display());
+
+        if let Some(directory) = path
+            .parent()
+            .filter(|directory| !directory.as_os_str().is_empty())
+        {
+            fs::create_dir_all(directory).map_err(|e| error(&e))?;
+        }
+
+        let writer = BufWriter::new(File::create(path).map_err(|e| error(&e))?);
+
+        let gif = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+
+        let output = if gif {
+            let (width, height) = size(scale);
+            let mut encoder =
+                gif::Encoder::new(writer, width as u16, height as u16, &color_table(palette))
+                    .map_err(|e| error(&e))?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(|e| error(&e))?;
+
+            Output::Gif {
+                encoder,
+                pending: None,
+                frames: 0,
+                hundredths: 0,
+            }
+        } else {
+            Output::Raw {
+                writer,
+                palette: *palette,
+            }
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            scale,
+            output,
+            frames: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the recording is an animated GIF, rather than raw RGBA frames.
+    pub fn is_gif(&self) -> bool {
+        matches!(self.output, Output::Gif { .. })
+    }
+
+    pub fn scale(&self) -> usize {
+        self.scale
+    }
+
+    /// Adds a frame, an image from `render` at the scale of the recording.
+    pub fn add_frame(&mut self, image: Vec<u8>) -> Result<(), String> {
+        self.frames += 1;
+
+        match &mut self.output {
+            Output::Gif {
+                pending,
+                frames,
+                hundredths,
+                ..
+            } => {
+                if let Some((previous, count)) = pending {
+                    if *previous == image {
+                        *count += 1;
+                        return Ok(());
+                    }
+
+                    if gif_delay(*frames + *count, *hundredths) < MIN_GIF_DELAY {
+                        *previous = image;
+                        *count += 1;
+                        return Ok(());
+                    }
+                }
+
+                let previous = pending.replace((image, 1));
+                self.write_gif_frame(previous)
+            }
+            Output::Raw { writer, palette } => {
+                let pixels: Vec<_> = image
+                    .iter()
+                    .flat_map(|intensity| palette.color(*intensity))
+                    .collect();
+
+                writer
+                    .write_all(&pixels)
+                    .map_err(|error| format!("Could not write {}: {error}", self.path.display()))
+            }
+        }
+    }
+
+    fn write_gif_frame(&mut self, frame: Option<(Vec<u8>, u32)>) -> Result<(), String> {
+        let (width, height) = size(self.scale);
+
+        let (encoder, frames, hundredths) = match &mut self.output {
+            Output::Gif {
+                encoder,
+                frames,
+                hundredths,
+                ..
+            } => (encoder, frames, hundredths),
+            Output::Raw { .. } => return Ok(()),
+        };
+
+        let (image, count) = match frame {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+
+        *frames += count;
+        let delay = gif_delay(*frames, *hundredths);
+        *hundredths += delay;
+
+        let frame = gif::Frame {
+            width: width as u16,
+            height: height as u16,
+            delay: delay.min(u16::MAX as u32) as u16,
+            buffer: image.into(),
+            ..Default::default()
+        };
+
+        encoder
+            .write_frame(&frame)
+            .map_err(|error| format!("Could not write {}: {error}", self.path.display()))
+    }
+
+    /// Writes what's left and closes the file. Returns how many frames were recorded.
+    pub fn finish(mut self) -> Result<u32, String> {
+        let pending = match &mut self.output {
+            Output::Gif { pending, .. } => pending.take(),
+            Output::Raw { .. } => None,
+        };
+        self.write_gif_frame(pending)?;
+
+        let error = |error: &dyn std::fmt::Display| {
+            format!("Could not write {}: {error}", self.path.display())
+        };
+
+        match self.output {
+            Output::Gif { encoder, .. } => {
+                encoder
+                    .into_inner()
+                    .map_err(|e| error(&e))?
+                    .flush()
+                    .map_err(|e| error(&e))?;
+            }
+            Output::Raw { mut writer, .. } => writer.flush().map_err(|e| error(&e))?,
+        }
+
+        Ok(self.frames)
+    }
+}
@@ -0,0 +1,310 @@
+use std::{collections::BTreeMap, fmt, fs, path::PathBuf, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{database, library, Emulator};
+
+/// How many bytes a search must be narrowed down to before they can be frozen.
+pub const MAX_FREEZE: usize = 8;
+
+/// A byte of the memory written every frame. Saved as `ADDRESS:VALUE`, e.g. `2F0:03`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Code {
+    pub address: u16,
+    pub value: u8,
+}
+
+impl FromStr for Code {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error =
+            || format!("Invalid cheat '{text}', expected ADDRESS:VALUE in hex like 2F0:03.");
+
+        let (address, value) = text.trim().split_once(':').ok_or_else(error)?;
+        let address = u16::from_str_radix(address, 16)
+            .ok()
+            .filter(|address| *address < 0x1000)
+            .ok_or_else(error)?;
+        let value = u8::from_str_radix(value, 16).map_err(|_| error())?;
+
+        Ok(Self { address, value })
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:03X}:{:02X}", self.address, self.value)
+    }
+}
+
+impl TryFrom<String> for Code {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<Code> for String {
+    fn from(code: Code) -> Self {
+        code.to_string()
+    }
+}
+
+/// A cheat of the list of a program, e.g. `{ "code": "2F0:03", "name": "Lives", "enabled": true }`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Cheat {
+    pub code: Code,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl fmt::Display for Cheat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} {name}", self.code),
+            None => write!(f, "{}", self.code),
+        }
+    }
+}
+
+/// The cheats of a program, saved by SHA-1 in `cheats_file`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cheats {
+    hash: String,
+    cheats: Vec<Cheat>,
+}
+
+impl Cheats {
+    /// No cheats for `program`.
+    pub fn new(program: &[u8]) -> Self {
+        Self {
+            hash: database::hash(program),
+            cheats: Vec::new(),
+        }
+    }
+
+    /// The cheats saved for `program`. A missing file has none.
+    pub fn load(program: &[u8]) -> Result<Self, String> {
+        let mut cheats = Self::new(program);
+
+        if let Some(saved) = read()?.remove(&cheats.hash) {
+            cheats.cheats = saved;
+        }
+
+        Ok(cheats)
+    }
+
+    /// Saves the cheats of the program, keeping the other programs' ones.
+    pub fn save(&self) -> Result<(), String> {
+        let file = cheats_file().ok_or("Could not find the data directory")?;
+
+        let mut saved = read()?;
+        if self.cheats.is_empty() {
+            saved.remove(&self.hash);
+        } else {
+            saved.insert(self.hash.clone(), self.cheats.clone());
+        }
+
+        let contents = serde_json::to_string_pretty(&saved).map_err(|error| error.to_string())?;
+
+        if let Some(directory) = file.parent() {
+            fs::create_dir_all(directory)
+                .map_err(|error| format!("Could not create {}: {error}", directory.display()))?;
+        }
+
+        fs::write(&file, contents)
+            .map_err(|error| format!("Could not write {}: {error}", file.display()))
+    }
+
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    /// Adds an enabled cheat, or changes the value of the one at the same address.
+    pub fn add(&mut self, code: Code) {
+        match self
+            .cheats
+            .iter_mut()
+            .find(|cheat| cheat.code.address == code.address)
+        {
+            Some(cheat) => {
+                cheat.code = code;
+                cheat.enabled = true;
+            }
+            None => self.cheats.push(Cheat {
+                code,
+                name: None,
+                enabled: true,
+            }),
+        }
+    }
+
+    /// Enables or disables the cheat at `index`, and returns it.
+    pub fn toggle(&mut self, index: usize) -> Option<&Cheat> {
+        let cheat = self.cheats.get_mut(index)?;
+        cheat.enabled = !cheat.enabled;
+
+        Some(cheat)
+    }
+
+    /// Writes the enabled cheats to the memory, once per frame.
+    pub fn apply(&self, emulator: &mut Emulator) {
+        for cheat in self.cheats.iter().filter(|cheat| cheat.enabled) {
+            emulator.poke(cheat.code.address, cheat.code.value);
+        }
+    }
+}
+
+/// Where the cheats are stored: `cheats.json` in the data directory, a list per SHA-1 hash.
+pub fn cheats_file() -> Option<PathBuf> {
+    library::data_dir().map(|directory| directory.join("cheats.json"))
+}
+
+/// The cheats of all the programs, by SHA-1.
+fn read() -> Result<BTreeMap<String, Vec<Cheat>>, String> {
+    let file = match cheats_file() {
+        Some(file) if file.exists() => file,
+        _ => return Ok(BTreeMap::new()),
+    };
+
+    fs::read_to_string(&file)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|error| error.to_string()))
+        .map_err(|error| format!("Could not read {}: {error}", file.display()))
+}
+
+/// Which bytes a search keeps, comparing the memory with the previous snapshot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal(u8),
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+impl Comparison {
+    fn keeps(self, before: u8, now: u8) -> bool {
+        match self {
+            Comparison::Equal(value) => now == value,
+            Comparison::Changed => now != before,
+            Comparison::Unchanged => now == before,
+            Comparison::Increased => now > before,
+            Comparison::Decreased => now < before,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Comparison::Equal(value) => write!(f, "= {value:02X}"),
+            Comparison::Changed => write!(f, "changed"),
+            Comparison::Unchanged => write!(f, "unchanged"),
+            Comparison::Increased => write!(f, "increased"),
+            Comparison::Decreased => write!(f, "decreased"),
+        }
+    }
+}
+
+/// A search of the memory for a counter (lives, score...): starts with every byte, and keeps the ones that
+/// compare with their previous value like the counter did, until a few are left.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Search {
+    snapshot: Vec<u8>,
+    candidates: Vec<u16>,
+    /// The comparisons so far.
+    steps: Vec<Comparison>,
+}
+
+impl Search {
+    pub fn new(memory: &[u8]) -> Self {
+        Self {
+            snapshot: memory.to_vec(),
+            candidates: (0..memory.len() as u16).collect(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Keeps the candidates that compare with the snapshot, and takes a new one. Returns how many are left.
+    pub fn narrow(&mut self, memory: &[u8], comparison: Comparison) -> usize {
+        let snapshot = &self.snapshot;
+
+        self.candidates.retain(|address| {
+            let address = *address as usize;
+            comparison.keeps(snapshot[address], memory[address])
+        });
+
+        self.snapshot = memory.to_vec();
+        self.steps.push(comparison);
+        self.candidates.len()
+    }
+
+    /// The addresses still matching, in order.
+    pub fn candidates(&self) -> &[u16] {
+        &self.candidates
+    }
+
+    pub fn steps(&self) -> &[Comparison] {
+        &self.steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quirks::Quirks;
+
+    #[test]
+    fn search_narrows_down_to_the_counter() {
+        let mut memory = vec![0; 8];
+        memory[3] = 3;
+        memory[5] = 3;
+        let mut search = Search::new(&memory);
+
+        // Lives are 3, then lost one while the byte 6 changed too, then nothing happened
+        assert_eq!(search.narrow(&memory, Comparison::Equal(3)), 2);
+        assert_eq!(search.candidates(), [3, 5]);
+
+        memory[3] = 2;
+        memory[6] = 1;
+        assert_eq!(search.narrow(&memory, Comparison::Changed), 1);
+        assert_eq!(search.candidates(), [3]);
+
+        memory[5] = 2;
+        assert_eq!(search.narrow(&memory, Comparison::Unchanged), 1);
+        assert_eq!(search.candidates(), [3]);
+        assert_eq!(
+            search.steps(),
+            [
+                Comparison::Equal(3),
+                Comparison::Changed,
+                Comparison::Unchanged
+            ]
+        );
+    }
+
+    #[test]
+    fn freeze_overwrites_the_program() {
+        // `LD I, #300`, `LD V0, 5`, `LD [I], V0` and a jump to the start
+        let program = [0xA3, 0x00, 0x60, 0x05, 0xF0, 0x55, 0x12, 0x00];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+        let mut cheats = Cheats::new(&program);
+        cheats.add("300:09".parse().unwrap());
+
+        for count in [3, 4] {
+            for _ in 0..count {
+                emulator.run().unwrap();
+            }
+            assert_eq!(emulator.memory()[0x300], 5);
+
+            cheats.apply(&mut emulator);
+            assert_eq!(emulator.memory()[0x300], 9);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{database::Settings, keymap::Keymap, library, palette::Palette, quirks::QuirkChange};
+
+/// The user's preferences, from `config.toml` in the configuration directory. Everything is optional:
+/// the speed, quirks, palette and keymap are the defaults for the programs the database doesn't know,
+/// and the flags of the command line take precedence over all of it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Instructions per frame.
+    pub speed: Option<u32>,
+    /// Changes to the quirks, like the `--quirks` flag, e.g. `["schip", "no-clip-sprites"]`.
+    pub quirks: Vec<QuirkChange>,
+    /// The colors of the unlit and lit pixels, e.g. `"#000000,#FFFFFF"`.
+    pub palette: Option<Palette>,
+    /// The initial size of the window, in pixels per CHIP-8 pixel.
+    pub scale: Option<usize>,
+    /// How many programs the launcher remembers.
+    pub recent_files: Option<usize>,
+    /// Where F12 saves the screenshots and recordings [default: the current directory].
+    pub captures: Option<PathBuf>,
+    pub audio: Audio,
+    /// The keys of the keypad pressed by the arrow keys, Space and Enter, e.g. `{ up = 5, down = 8 }`.
+    pub keymap: Option<Keymap>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Audio {
+    /// Whether the sound timer beeps.
+    pub enabled: bool,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl Config {
+    /// Reads the configuration file. A missing file is an empty configuration.
+    pub fn load() -> Result<Self, String> {
+        match file() {
+            Some(file) if file.exists() => Self::read(&file),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    fn read(file: &Path) -> Result<Self, String> {
+        fs::read_to_string(file)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| toml::from_str(&contents).map_err(|error| error.to_string()))
+            .map_err(|error| format!("Could not read {}: {error}", file.display()))
+    }
+
+    /// Writes the configuration file, and returns where.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let file = file().ok_or("Could not find the configuration directory")?;
+        let contents = toml::to_string_pretty(self).map_err(|error| error.to_string())?;
+
+        if let Some(directory) = file.parent() {
+            fs::create_dir_all(directory)
+                .map_err(|error| format!("Could not create {}: {error}", directory.display()))?;
+        }
+
+        fs::write(&file, contents)
+            .map_err(|error| format!("Could not write {}: {error}", file.display()))?;
+
+        Ok(file)
+    }
+
+    /// The settings of a program the database doesn't know.
+    pub fn defaults(&self) -> Settings {
+        let mut settings = Settings::default();
+
+        for change in &self.quirks {
+            change.apply(&mut settings.quirks);
+        }
+
+        if let Some(speed) = self.speed {
+            settings.instructions_per_frame = speed;
+        }
+
+        if let Some(palette) = self.palette {
+            settings.palette = palette;
+        }
+
+        if let Some(keymap) = self.keymap {
+            settings.keymap = keymap;
+        }
+
+        settings.audio = self.audio.enabled;
+        settings
+    }
+
+    pub fn recent_files(&self) -> usize {
+        self.recent_files.unwrap_or(library::DEFAULT_RECENT_FILES)
+    }
+
+    /// Where the screenshots and recordings are saved.
+    pub fn captures(&self) -> PathBuf {
+        self.captures.clone().unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Makes the settings of the running program and the scale of the window the defaults.
+    pub fn remember(&mut self, settings: &Settings, scale: usize) {
+        self.speed = Some(settings.instructions_per_frame);
+        self.quirks = QuirkChange::all(settings.quirks).to_vec();
+        self.palette = Some(settings.palette);
+        self.scale = Some(scale);
+        self.audio.enabled = settings.audio;
+        self.keymap = Some(settings.keymap);
+    }
+}
+
+/// Where the configuration is stored: `$XDG_CONFIG_HOME/rusty8`, `~/.config/rusty8` or `%APPDATA%\rusty8`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+    Some(base.join("rusty8"))
+}
+
+/// The configuration file, `config.toml` in the configuration directory.
+pub fn file() -> Option<PathBuf> {
+    config_dir().map(|directory| directory.join("config.toml"))
+}
@@ -0,0 +1,182 @@
+use std::{fmt::Write, ops::Range};
+
+/// How many bytes a line of the map shows.
+const BYTES_PER_LINE: usize = 64;
+
+/// How the program used a byte of the memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Run as (part of) an instruction.
+    Executed = 1,
+    /// Read as data: a sprite (DXYN) or registers (FX65).
+    Read = 2,
+    /// Written, e.g. by FX33 or FX55.
+    Written = 4,
+}
+
+/// Which bytes of the memory were executed, read or written since the program was loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Coverage {
+    accesses: Vec<u8>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self {
+            accesses: vec![0; 4096],
+        }
+    }
+
+    pub(crate) fn mark(&mut self, address: usize, access: Access) {
+        let length = self.accesses.len();
+        self.accesses[address % length] |= access as u8;
+    }
+
+    pub fn has(&self, address: u16, access: Access) -> bool {
+        self.accesses
+            .get(address as usize)
+            .is_some_and(|accesses| accesses & access as u8 != 0)
+    }
+
+    fn touched(&self, address: u16) -> bool {
+        self.accesses
+            .get(address as usize)
+            .is_some_and(|accesses| *accesses != 0)
+    }
+
+    /// A byte of the map: `x` executed, `r` read, `w` only written, `X` and `R` when also written, `.` untouched.
+    pub fn symbol(&self, address: u16) -> char {
+        let written = self.has(address, Access::Written);
+
+        if self.has(address, Access::Executed) {
+            if written {
+                'X'
+            } else {
+                'x'
+            }
+        } else if self.has(address, Access::Read) {
+            if written {
+                'R'
+            } else {
+                'r'
+            }
+        } else if written {
+            'w'
+        } else {
+            '.'
+        }
+    }
+
+    /// How much of the `program` was executed, read and written, and how much wasn't used at all.
+    pub fn summary(&self, program: Range<u16>) -> String {
+        let total = program.len().max(1);
+        let count = |access: Option<Access>| {
+            program
+                .clone()
+                .filter(|&address| match access {
+                    Some(access) => self.has(address, access),
+                    None => !self.touched(address),
+                })
+                .count()
+        };
+
+        let line = |name: &str, bytes: usize| {
+            format!(
+                "{name:<10}{bytes:>5} bytes  {:>5.1}%\n",
+                bytes as f64 * 100.0 / total as f64
+            )
+        };
+
+        [
+            line("Executed", count(Some(Access::Executed))),
+            line("Read", count(Some(Access::Read))),
+            line("Written", count(Some(Access::Written))),
+            line("Untouched", count(None)),
+        ]
+        .concat()
+    }
+
+    /// The memory as a map of `symbol`s: the lines of the `program`, and the other lines the program used
+    /// (like the font, or its variables). The bytes outside of the program that it didn't use are blank.
+    pub fn map(&self, program: Range<u16>) -> String {
+        let mut map = String::from(
+            "x executed, r read, w written, X/R executed/read and written, . untouched\n",
+        );
+        let mut skipped = false;
+
+        for start in (0..self.accesses.len()).step_by(BYTES_PER_LINE) {
+            let line = start as u16..(start + BYTES_PER_LINE) as u16;
+            let in_program = line.start < program.end && program.start < line.end;
+
+            if !in_program && !line.clone().any(|address| self.touched(address)) {
+                skipped = true;
+                continue;
+            }
+
+            if std::mem::take(&mut skipped) {
+                map.push_str("...\n");
+            }
+
+            // Only the bytes of the program, or the ones it used
+            let symbols: String = line
+                .clone()
+                .map(|address| {
+                    if program.contains(&address) || self.touched(address) {
+                        self.symbol(address)
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+            let _ = writeln!(map, "{:03X}  {}", line.start, symbols.trim_end());
+        }
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{quirks::Quirks, Emulator};
+
+    #[test]
+    fn program_reading_its_data() {
+        let program = [
+            0xA2, 0x0A, // LD I, #20A
+            0xF1, 0x65, // LD V1, [I]
+            0xA2, 0x0C, // LD I, #20C
+            0xF1, 0x33, // LD B, V1
+            0x12, 0x08, // JP #208
+            0x12, 0x34, // 20A: data
+            0x00, 0x00, 0x00, // 20C: the digits of V1
+        ];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+
+        for _ in 0..6 {
+            emulator.run().unwrap();
+        }
+
+        let coverage = emulator.coverage();
+        let symbols: String = (0x200..0x20F)
+            .map(|address| coverage.symbol(address))
+            .collect();
+        assert_eq!(symbols, "xxxxxxxxxxrrwww");
+        assert!(coverage.has(0x20B, Access::Read));
+        assert!(!coverage.has(0x20B, Access::Executed));
+        assert_eq!(&emulator.memory()[0x20C..0x20F], [0, 5, 2]);
+        assert_eq!(
+            coverage.summary(emulator.program_region()),
+            "Executed     10 bytes   66.7%\n\
+             Read          2 bytes   13.3%\n\
+             Written       3 bytes   20.0%\n\
+             Untouched     0 bytes    0.0%\n"
+        );
+    }
+}
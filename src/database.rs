@@ -0,0 +1,306 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    keymap::Keymap,
+    library,
+    palette::{self, Palette},
+    quirks::Quirks,
+    scheduler, PROGRAM_START,
+};
+
+// The CHIP-8 database (https://github.com/chip-8/chip-8-database), see database/LICENSE.md
+static PROGRAMS: &str = include_str!("../database/programs.json");
+static HASHES: &str = include_str!("../database/sha1-hashes.json");
+static PLATFORMS: &str = include_str!("../database/platforms.json");
+
+/// A program of `programs.json`, with all its known versions.
+#[derive(Deserialize)]
+struct Program {
+    title: String,
+    roms: HashMap<String, Rom>,
+}
+
+/// A version of a program, by SHA-1. Everything is optional, so the user overrides can be partial.
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rom {
+    /// The platforms the program runs on, the preferred one first.
+    #[serde(default)]
+    pub platforms: Vec<String>,
+    /// Platforms the program runs on, with some of their quirks changed.
+    #[serde(default)]
+    pub quirky_platforms: HashMap<String, PlatformQuirks>,
+    /// Instructions per frame.
+    pub tickrate: Option<u32>,
+    pub colors: Option<Colors>,
+    /// The keys of the hex keypad the program uses for its controls.
+    pub keys: Option<Keymap>,
+    pub start_address: Option<u16>,
+}
+
+impl Rom {
+    /// This, with the missing values taken from `other`.
+    fn or(self, other: Rom) -> Rom {
+        Rom {
+            platforms: if self.platforms.is_empty() {
+                other.platforms
+            } else {
+                self.platforms
+            },
+            quirky_platforms: if self.quirky_platforms.is_empty() {
+                other.quirky_platforms
+            } else {
+                self.quirky_platforms
+            },
+            tickrate: self.tickrate.or(other.tickrate),
+            colors: self.colors.or(other.colors),
+            keys: self.keys.or(other.keys),
+            start_address: self.start_address.or(other.start_address),
+        }
+    }
+
+    fn platform(&self) -> Option<&str> {
+        self.platforms
+            .first()
+            .or_else(|| self.quirky_platforms.keys().next())
+            .map(String::as_str)
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+pub struct Colors {
+    /// The background, then the lit pixels (more for XO-CHIP's planes).
+    #[serde(default)]
+    pub pixels: Vec<String>,
+}
+
+/// The quirks of `platforms.json`. Only `wrap`, `vblank` and `jump` have an equivalent in `Quirks` for now.
+#[derive(Clone, Copy, Default, Deserialize)]
+pub struct PlatformQuirks {
+    pub wrap: Option<bool>,
+    pub vblank: Option<bool>,
+    pub jump: Option<bool>,
+}
+
+impl PlatformQuirks {
+    fn apply(&self, quirks: &mut Quirks) {
+        if let Some(wrap) = self.wrap {
+            quirks.clip_sprites = !wrap;
+        }
+
+        if let Some(vblank) = self.vblank {
+            quirks.display_wait = vblank;
+        }
+
+        if let Some(jump) = self.jump {
+            quirks.jump_with_vx = jump;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Platform {
+    id: String,
+    default_tickrate: u32,
+    quirks: PlatformQuirks,
+}
+
+/// How to run a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub title: Option<String>,
+    /// The id of the platform in `platforms.json`, e.g. `originalChip8` or `superchip`.
+    pub platform: Option<String>,
+    pub quirks: Quirks,
+    pub instructions_per_frame: u32,
+    pub palette: Palette,
+    pub keymap: Keymap,
+    pub load_address: u16,
+    /// Whether the sound timer beeps.
+    pub audio: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            title: None,
+            platform: None,
+            quirks: Quirks::default(),
+            instructions_per_frame: scheduler::DEFAULT_INSTRUCTIONS_PER_FRAME,
+            palette: Palette::default(),
+            keymap: Keymap::default(),
+            load_address: PROGRAM_START,
+            audio: true,
+        }
+    }
+}
+
+/// The settings of known programs, looked up by the SHA-1 of the program.
+pub struct Database {
+    programs: Vec<Program>,
+    hashes: HashMap<String, usize>,
+    platforms: Vec<Platform>,
+    /// The user's own settings, which take precedence over the database.
+    overrides: HashMap<String, Rom>,
+}
+
+impl Database {
+    /// The database built into the executable.
+    pub fn bundled() -> Self {
+        Self {
+            programs: serde_json::from_str(PROGRAMS).expect("The bundled programs.json is invalid"),
+            hashes: serde_json::from_str(HASHES).expect("The bundled sha1-hashes.json is invalid"),
+            platforms: serde_json::from_str(PLATFORMS)
+                .expect("The bundled platforms.json is invalid"),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// The bundled database, with the user overrides from `overrides_file` if there are any.
+    pub fn load() -> Result<Self, String> {
+        let mut database = Self::bundled();
+
+        if let Some(file) = overrides_file() {
+            if let Ok(contents) = fs::read_to_string(&file) {
+                database.overrides = serde_json::from_str(&contents)
+                    .map_err(|error| format!("Could not read {}: {error}", file.display()))?;
+            }
+        }
+
+        Ok(database)
+    }
+
+    /// The settings for `program`: the defaults, changed by what the database and the user overrides say about it.
+    pub fn settings(&self, program: &[u8]) -> Settings {
+        self.settings_from(Settings::default(), program)
+    }
+
+    /// Like `settings`, starting from the user's own defaults.
+    pub fn settings_from(&self, defaults: Settings, program: &[u8]) -> Settings {
+        let hash = hash(program);
+        let mut settings = defaults;
+
+        let known = self
+            .hashes
+            .get(&hash)
+            .and_then(|&index| self.programs.get(index));
+        let rom = match (
+            self.overrides.get(&hash),
+            known.and_then(|program| program.roms.get(&hash)),
+        ) {
+            (Some(user), Some(rom)) => user.clone().or(rom.clone()),
+            (Some(rom), None) | (None, Some(rom)) => rom.clone(),
+            (None, None) => return settings,
+        };
+
+        settings.title = known.map(|program| program.title.clone());
+
+        if let Some(id) = rom.platform() {
+            self.apply_platform(&mut settings, id);
+
+            if let Some(quirks) = rom.quirky_platforms.get(id) {
+                quirks.apply(&mut settings.quirks);
+            }
+        }
+
+        if let Some(tickrate) = rom.tickrate {
+            settings.instructions_per_frame = tickrate;
+        }
+
+        if let Some(colors) = &rom.colors {
+            if let [background, foreground, ..] = colors.pixels.as_slice() {
+                if let (Ok(background), Ok(foreground)) = (
+                    palette::parse_color(background),
+                    palette::parse_color(foreground),
+                ) {
+                    settings.palette = Palette::new(background, foreground);
+                }
+            }
+        }
+
+        if let Some(keys) = rom.keys {
+            settings.keymap = keys;
+        }
+
+        if let Some(address) = rom.start_address.filter(|address| *address < 0x1000) {
+            settings.load_address = address;
+        }
+
+        settings
+    }
+
+    /// The ids of the platforms, e.g. `originalChip8` or `superchip`.
+    pub fn platforms(&self) -> impl Iterator<Item = &str> {
+        self.platforms.iter().map(|platform| platform.id.as_str())
+    }
+
+    /// Sets the platform, with its quirks and speed. Returns false if there's no such platform.
+    pub fn apply_platform(&self, settings: &mut Settings, id: &str) -> bool {
+        let platform = match self.platforms.iter().find(|platform| platform.id == id) {
+            Some(platform) => platform,
+            None => return false,
+        };
+
+        settings.platform = Some(id.to_string());
+
+        // Only SUPER-CHIP counts the collided rows, the database doesn't have a quirk for it
+        settings.quirks = if id.starts_with("superchip") {
+            Quirks::schip()
+        } else {
+            Quirks::default()
+        };
+
+        platform.quirks.apply(&mut settings.quirks);
+        settings.instructions_per_frame = platform.default_tickrate;
+
+        true
+    }
+}
+
+/// The SHA-1 of a program, in lowercase hex like in the database.
+pub fn hash(program: &[u8]) -> String {
+    sha1_smol::Sha1::from(program).digest().to_string()
+}
+
+/// Where the user overrides are stored: `overrides.json` in the data directory.
+/// It maps SHA-1 hashes to ROM entries in the format of `programs.json`, e.g.
+/// `{ "0123...": { "platforms": ["superchip"], "tickrate": 30, "keys": { "up": 5 } } }`.
+pub fn overrides_file() -> Option<PathBuf> {
+    library::data_dir().map(|directory| directory.join("overrides.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vblank_is_the_display_wait() {
+        let database = Database::bundled();
+        let mut settings = Settings::default();
+
+        assert!(database.apply_platform(&mut settings, "originalChip8"));
+        assert!(settings.quirks.display_wait);
+
+        assert!(database.apply_platform(&mut settings, "superchip"));
+        assert!(!settings.quirks.display_wait);
+    }
+
+    #[test]
+    fn rom_quirks_change_the_platform_ones() {
+        let program = [0x12, 0x00];
+        let mut database = Database::bundled();
+        database.overrides = serde_json::from_str(&format!(
+            r#"{{ "{}": {{ "platforms": ["originalChip8"], "quirkyPlatforms": {{ "originalChip8": {{ "vblank": false }} }} }} }}"#,
+            hash(&program)
+        ))
+        .unwrap();
+
+        let settings = database.settings(&program);
+        assert_eq!(settings.platform.as_deref(), Some("originalChip8"));
+        assert!(settings.quirks.clip_sprites);
+        assert!(!settings.quirks.display_wait);
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Write;
+
+use crate::{
+    coverage::{Access, Coverage},
+    instruction::Instruction,
+};
+
+/// A listing of `program` loaded at `load_address`: one line per two bytes, with the address, the opcode and its mnemonic
+/// (see `Instruction`'s `Display`).
+pub fn listing(program: &[u8], load_address: u16) -> String {
+    let mut listing = String::new();
+
+    for (index, bytes) in program.chunks(2).enumerate() {
+        let address = load_address as usize + index * 2;
+
+        let _ = match *bytes {
+            [high, low] => {
+                let opcode = u16::from_be_bytes([high, low]);
+                writeln!(
+                    listing,
+                    "{address:03X}  {opcode:04X}  {}",
+                    Instruction::decode(opcode)
+                )
+            }
+            // A program of an odd length ends with a single byte
+            [byte] => writeln!(listing, "{address:03X}  {byte:02X}    DB #{byte:02X}"),
+            _ => unreachable!(),
+        };
+    }
+
+    listing
+}
+
+/// A listing of `program` like `listing`, telling the code from the data with what the program did (`coverage`):
+/// the executed bytes are decoded from the address they ran at, and the bytes that were only read or written are shown
+/// as `DB`. Each line shows how its bytes were used (see `Coverage::symbol`).
+pub fn annotated(program: &[u8], load_address: u16, coverage: &Coverage) -> String {
+    let mut listing = String::new();
+    let address = |offset: usize| (load_address as usize + offset) as u16;
+    let data = |offset: usize| {
+        coverage.has(address(offset), Access::Read)
+            || coverage.has(address(offset), Access::Written)
+    };
+
+    let mut offset = 0;
+
+    while offset < program.len() {
+        let start = address(offset);
+        let paired = offset + 1 < program.len();
+
+        // The bytes nothing used are shown as instructions, unless the next one starts something else
+        let untouched_pair = paired
+            && !data(offset)
+            && !data(offset + 1)
+            && !coverage.has(address(offset + 1), Access::Executed);
+
+        if paired && (coverage.has(start, Access::Executed) || untouched_pair) {
+            let opcode = u16::from_be_bytes([program[offset], program[offset + 1]]);
+            let _ = writeln!(
+                listing,
+                "{start:03X}  {opcode:04X}  {}{}  {}",
+                coverage.symbol(start),
+                coverage.symbol(address(offset + 1)),
+                Instruction::decode(opcode)
+            );
+            offset += 2;
+        } else {
+            let byte = program[offset];
+            let _ = writeln!(
+                listing,
+                "{start:03X}  {byte:02X}    {}   DB #{byte:02X}",
+                coverage.symbol(start)
+            );
+            offset += 1;
+        }
+    }
+
+    listing
+}
@@ -0,0 +1,943 @@
+use std::ops::Range;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    coverage::{Access, Coverage},
+    instruction::Instruction,
+    quirks::Quirks,
+    rect,
+    rect::Rect,
+};
+
+pub const CHIP8_WIDTH: usize = 64;
+pub const CHIP8_HEIGHT: usize = 32;
+
+pub const SCHIP_WIDTH: usize = 128;
+pub const SCHIP_HEIGHT: usize = 64;
+
+/// Where programs are usually loaded and started.
+pub const PROGRAM_START: u16 = 0x200;
+
+pub struct Emulator {
+    pub(crate) registers: [u8; 16],
+
+    stack: [u16; 16],
+    /// How many calls are on the stack.
+    pub(crate) stack_pointer: u8,
+
+    memory: [u8; 4096],
+    /// The bytes the program wrote since the last call to `take_written`.
+    written: [bool; 4096],
+    /// How the program used each byte since it was loaded.
+    coverage: Coverage,
+    /// The instruction starting at each address, decoded when the memory is loaded or written
+    /// rather than every time it runs.
+    decoded: [Instruction; 4096],
+    pub(crate) address: u16,
+    pub(crate) program_counter: u16,
+
+    pub(crate) screen: [[u8; SCHIP_HEIGHT]; SCHIP_WIDTH],
+    hires: bool,
+    dirty: Option<Rect>,
+
+    keyboard: [bool; 16],
+    waiting_for_keypress: bool,
+    released_key: Option<u8>,
+
+    pub(crate) delay_timer: u8,
+    pub(crate) sound_timer: u8,
+
+    vblank: bool,
+    pub(crate) waiting_for_vblank: bool,
+
+    quirks: Quirks,
+    rng: SmallRng,
+    /// The seed of `rng`, so that a reset runs the program like the first time.
+    seed: u64,
+
+    /// The program and where it was loaded, for the resets.
+    program: Vec<u8>,
+    load_address: u16,
+}
+
+impl Emulator {
+    /// Loads `program` at 0x200. `seed` initializes the random number generator.
+    pub fn new(program: &[u8], quirks: Quirks, seed: u64) -> Self {
+        Self::with_load_address(program, PROGRAM_START, quirks, seed)
+    }
+
+    /// Checks that `program` fits in the memory after `load_address`: `with_load_address` leaves out what doesn't.
+    pub fn check_size(program: &[u8], load_address: u16) -> Result<(), String> {
+        let space = 0x1000usize.saturating_sub(load_address as usize);
+
+        if program.len() > space {
+            return Err(format!(
+                "The program is {} bytes long, only {space} fit in the memory after {load_address:#05X}.",
+                program.len()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Loads `program` at `load_address` (below 0x1000) and starts running it from there.
+    pub fn with_load_address(program: &[u8], load_address: u16, quirks: Quirks, seed: u64) -> Self {
+        let mut emulator = Self {
+            registers: [0u8; 16],
+
+            stack: [0u16; 16],
+            stack_pointer: 0,
+
+            memory: [0u8; 4096],
+            written: [false; 4096],
+            coverage: Coverage::new(),
+            decoded: [Instruction::ClearScreen; 4096],
+            address: 0,
+            program_counter: load_address,
+
+            screen: [[0u8; SCHIP_HEIGHT]; SCHIP_WIDTH],
+            hires: false,
+            dirty: None,
+
+            keyboard: [false; 16],
+            waiting_for_keypress: false,
+            released_key: None,
+
+            delay_timer: 0,
+            sound_timer: 0,
+
+            vblank: false,
+            waiting_for_vblank: false,
+
+            quirks,
+            rng: SmallRng::seed_from_u64(seed),
+            seed,
+
+            program: program.to_vec(),
+            load_address,
+        };
+
+        // Load the font
+        static FONT: [u8; 16 * 5] = [
+            0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+            0x20, 0x60, 0x20, 0x20, 0x70, // 1
+            0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+            0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+            0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+            0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+            0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+            0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+            0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+            0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+            0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+            0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+            0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+            0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+            0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+            0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+        ];
+        emulator.memory[..0x50].copy_from_slice(&FONT);
+
+        // Load the program
+        let data = &mut emulator.memory[load_address as usize..];
+        let length = program.len().min(data.len());
+        data[..length].copy_from_slice(&program[..length]);
+
+        for address in 0..emulator.memory.len() {
+            emulator.decoded[address] = emulator.decode(address);
+        }
+
+        emulator
+    }
+
+    /// Replaces the running program with `program`, starting over with a new seed.
+    pub fn load(&mut self, program: &[u8], load_address: u16, quirks: Quirks) {
+        let seed = self.rng.gen();
+        self.load_with_seed(program, load_address, quirks, seed);
+    }
+
+    /// Reloads the program and clears everything else, like turning the machine off and on.
+    /// The random numbers are the same as the first time.
+    pub fn reset(&mut self) {
+        let program = std::mem::take(&mut self.program);
+        self.load_with_seed(&program, self.load_address, self.quirks, self.seed);
+    }
+
+    fn load_with_seed(&mut self, program: &[u8], load_address: u16, quirks: Quirks, seed: u64) {
+        *self = Self::with_load_address(program, load_address, quirks, seed);
+        self.dirty = Some(Rect::new(0, 0, SCHIP_WIDTH, SCHIP_HEIGHT));
+    }
+
+    /// Restarts the program without reloading it: the memory and the screen are kept
+    /// (including what the program wrote), the CPU starts over from the load address.
+    pub fn soft_reset(&mut self) {
+        self.registers = [0; 16];
+        self.stack = [0; 16];
+        self.stack_pointer = 0;
+        self.address = 0;
+        self.program_counter = self.load_address;
+
+        self.waiting_for_keypress = false;
+        self.released_key = None;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.waiting_for_vblank = false;
+    }
+
+    /// Fetches the instruction at the program counter, and moves to the next one.
+    fn fetch(&mut self) -> Result<Instruction, String> {
+        let pc = self.program_counter as usize;
+        if pc + 1 >= self.memory.len() {
+            return Err(format!("The program counter left the memory at {pc:03X}"));
+        }
+
+        self.coverage.mark(pc, Access::Executed);
+        self.coverage.mark(pc + 1, Access::Executed);
+        self.program_counter += 2;
+
+        Ok(self.decoded[pc])
+    }
+
+    /// The instruction at `address`, e.g. to show it in a debugger.
+    pub fn instruction_at(&self, address: u16) -> Instruction {
+        self.decoded[address as usize % self.decoded.len()]
+    }
+
+    /// The instruction about to run.
+    pub fn next_instruction(&self) -> Instruction {
+        self.instruction_at(self.program_counter)
+    }
+
+    /// Runs a single instruction.
+    /// Fails on the instructions that aren't supported, like the machine code routines, and leaves the program counter
+    /// on them.
+    pub fn run(&mut self) -> Result<(), String> {
+        let instruction = self.fetch()?;
+
+        match instruction {
+            Instruction::ClearScreen => self.clear_screen(),
+            Instruction::Return => self.fn_return()?,
+            Instruction::LowRes => self.set_hires(false),
+            Instruction::HighRes => self.set_hires(true),
+            Instruction::Jump { address } => self.jump(address),
+            Instruction::Call { address } => self.fn_call(address)?,
+            Instruction::SkipIfEqual { x, byte } => self.skip_if_variable_is_equal_to(x, byte),
+            Instruction::SkipIfNotEqual { x, byte } => {
+                self.skip_if_variable_is_not_equal_to(x, byte)
+            }
+            Instruction::SkipIfRegistersEqual { x, y } => self.skip_if_variables_equal(x, y),
+            Instruction::Load { x, byte } => self.set_register_to(x, byte),
+            Instruction::Add { x, byte } => self.add_to_variable(x, byte),
+            Instruction::Move { x, y } => self.op_assignment(x, y),
+            Instruction::Or { x, y } => self.op_or(x, y),
+            Instruction::And { x, y } => self.op_and(x, y),
+            Instruction::Xor { x, y } => self.op_xor(x, y),
+            Instruction::AddRegisters { x, y } => self.op_add(x, y),
+            Instruction::Subtract { x, y } => self.op_sub(x, y),
+            Instruction::ShiftRight { x, .. } => self.op_shift_right(x),
+            Instruction::SubtractFrom { x, y } => self.op_sub_assign(x, y),
+            Instruction::ShiftLeft { x, .. } => self.op_shift_left(x),
+            Instruction::SkipIfRegistersNotEqual { x, y } => self.skip_if_variables_not_equal(x, y),
+            Instruction::LoadAddress { address } => self.set_address_to(address),
+            Instruction::JumpWithOffset { address } => self.jump_add(address),
+            Instruction::Random { x, byte } => self.bitwise_and_with_random(x, byte),
+            Instruction::Draw { x, y, rows } => self.draw_sprite(x, y, rows),
+            Instruction::SkipIfPressed { x } => self.skip_if_pressed(x),
+            Instruction::SkipIfNotPressed { x } => self.skip_if_not_pressed(x),
+            Instruction::LoadDelayTimer { x } => self.set_variable_to_delay_timer(x),
+            Instruction::WaitForKey { x } => self.get_key_press(x),
+            Instruction::SetDelayTimer { x } => self.set_delay_timer_to(x),
+            Instruction::SetSoundTimer { x } => self.set_sound_timer_to(x),
+            Instruction::AddToAddress { x } => self.add_variable_to_address(x),
+            Instruction::LoadFont { x } => self.sets_address_to_font_sprite(x),
+            Instruction::StoreDecimal { x } => self.store_variable_as_binary(x),
+            Instruction::StoreRegisters { x } => self.dump_registers_into_memory(x),
+            Instruction::LoadRegisters { x } => self.load_registers_from_memory(x),
+            _ => {
+                self.program_counter -= 2;
+                return Err(format!(
+                    "Unsupported instruction {instruction} at {:03X}",
+                    self.program_counter
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A key of the hex keypad was pressed.
+    pub fn key_down(&mut self, key: u8) {
+        self.keyboard[key as usize] = true;
+    }
+
+    /// A key of the hex keypad was released.
+    pub fn key_up(&mut self, key: u8) {
+        self.keyboard[key as usize] = false;
+
+        if self.waiting_for_keypress {
+            self.released_key = Some(key);
+        }
+    }
+
+    /// Called by the scheduler at the end of every frame (60 times per second): counts down the timers
+    /// and releases a sprite draw that is waiting for the display.
+    pub fn vertical_blank(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+
+        if self.waiting_for_vblank {
+            self.waiting_for_vblank = false;
+            self.vblank = true;
+        }
+    }
+
+    /// The size of the visible screen in the current display mode.
+    pub fn resolution(&self) -> (usize, usize) {
+        if self.hires {
+            (SCHIP_WIDTH, SCHIP_HEIGHT)
+        } else {
+            (CHIP8_WIDTH, CHIP8_HEIGHT)
+        }
+    }
+
+    /// The 4KB of memory: the font at 0x000, then the program.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Which bytes were executed, read and written since the program was loaded.
+    pub fn coverage(&self) -> &Coverage {
+        &self.coverage
+    }
+
+    /// The pixels, by column then row. Only the top left `resolution()` part is visible.
+    pub fn screen(&self) -> &[[u8; SCHIP_HEIGHT]; SCHIP_WIDTH] {
+        &self.screen
+    }
+
+    /// The program that was loaded, as it was in the file.
+    pub fn program(&self) -> &[u8] {
+        &self.program
+    }
+
+    /// Where the program was loaded, clamped to the memory.
+    pub fn program_region(&self) -> Range<u16> {
+        let end = (self.load_address as usize + self.program.len()).min(self.memory.len());
+        self.load_address..end as u16
+    }
+
+    /// The addresses the program wrote to since the last call, in order.
+    pub fn take_written(&mut self) -> Vec<u16> {
+        let written = (0..self.written.len() as u16)
+            .filter(|address| self.written[*address as usize])
+            .collect();
+
+        self.written = [false; 4096];
+        written
+    }
+
+    /// Changes a byte of the memory, e.g. from the debugger.
+    pub fn poke(&mut self, address: u16, value: u8) {
+        let address = address as usize % self.memory.len();
+
+        self.memory[address] = value;
+        self.redecode(address);
+    }
+
+    /// Writes a byte of the memory for the program, keeping track of it. The address wraps around the memory.
+    fn write(&mut self, address: u16, value: u8) {
+        let address = address as usize % self.memory.len();
+
+        self.memory[address] = value;
+        self.redecode(address);
+        self.written[address] = true;
+        self.coverage.mark(address, Access::Written);
+    }
+
+    /// Decodes the instruction starting at `address` (the last one wraps around to the start of the memory).
+    fn decode(&self, address: usize) -> Instruction {
+        let next = (address + 1) % self.memory.len();
+        Instruction::decode(u16::from_be_bytes([
+            self.memory[address],
+            self.memory[next],
+        ]))
+    }
+
+    /// Decodes again the instructions that the byte at `address` is a part of, after it changed:
+    /// the one starting there, and the one before.
+    fn redecode(&mut self, address: usize) {
+        let previous = (address + self.memory.len() - 1) % self.memory.len();
+
+        self.decoded[previous] = self.decode(previous);
+        self.decoded[address] = self.decode(address);
+    }
+
+    /// The part of the screen that changed since the last call.
+    pub fn take_dirty(&mut self) -> Option<Rect> {
+        self.dirty.take()
+    }
+
+    /// Clears the screen.
+    fn clear_screen(&mut self) {
+        self.screen = [[0u8; SCHIP_HEIGHT]; SCHIP_WIDTH];
+        self.dirty = Some(Rect::new(0, 0, SCHIP_WIDTH, SCHIP_HEIGHT));
+    }
+
+    /// Switches between the 64x32 and the 128x64 (SCHIP) display modes. The screen is cleared.
+    fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.clear_screen();
+    }
+
+    /// Jumps to address NNN.
+    fn jump(&mut self, address: u16) {
+        self.program_counter = address;
+    }
+
+    /// Calls subroutine at NNN. Fails when the stack is full, leaving the program counter on the call.
+    fn fn_call(&mut self, address: u16) -> Result<(), String> {
+        if self.stack_pointer as usize == self.stack.len() {
+            self.program_counter -= 2;
+            return Err(format!("Stack overflow at {:03X}", self.program_counter));
+        }
+
+        self.stack[self.stack_pointer as usize] = self.program_counter;
+        self.stack_pointer += 1;
+
+        self.program_counter = address;
+        Ok(())
+    }
+
+    /// Returns from a subroutine. Fails when there's nothing to return to, leaving the program counter on the return.
+    fn fn_return(&mut self) -> Result<(), String> {
+        if self.stack_pointer == 0 {
+            self.program_counter -= 2;
+            return Err(format!(
+                "Return with an empty stack at {:03X}",
+                self.program_counter
+            ));
+        }
+
+        self.stack_pointer -= 1;
+        self.program_counter = self.stack[self.stack_pointer as usize];
+        Ok(())
+    }
+
+    /// Skips the next instruction if VX equals NN.
+    fn skip_if_variable_is_equal_to(&mut self, vx: u8, value: u8) {
+        if self.registers[vx as usize] == value {
+            self.program_counter += 2;
+        }
+    }
+
+    /// Skips the next instruction if VX does not equal NN.
+    fn skip_if_variable_is_not_equal_to(&mut self, vx: u8, value: u8) {
+        if self.registers[vx as usize] != value {
+            self.program_counter += 2;
+        }
+    }
+
+    /// Skips the next instruction if VX equals VY.
+    fn skip_if_variables_equal(&mut self, vx: u8, vy: u8) {
+        if self.registers[vx as usize] == self.registers[vy as usize] {
+            self.program_counter += 2;
+        }
+    }
+
+    /// Skips the next instruction if VX does not equal VY.
+    fn skip_if_variables_not_equal(&mut self, vx: u8, vy: u8) {
+        if self.registers[vx as usize] != self.registers[vy as usize] {
+            self.program_counter += 2;
+        }
+    }
+
+    /// Sets VX to NN.
+    fn set_register_to(&mut self, vx: u8, value: u8) {
+        self.registers[vx as usize] = value;
+    }
+
+    /// Adds NN to VX (carry flag is not changed)
+    fn add_to_variable(&mut self, vx: u8, value: u8) {
+        self.registers[vx as usize] = self.registers[vx as usize].overflowing_add(value).0;
+    }
+
+    /// Sets VX to the value of VY.
+    fn op_assignment(&mut self, vx: u8, vy: u8) {
+        self.registers[vx as usize] = self.registers[vy as usize];
+    }
+
+    /// Sets VX to VX or VY. (bitwise OR operation)
+    fn op_or(&mut self, vx: u8, vy: u8) {
+        self.registers[vx as usize] |= self.registers[vy as usize];
+    }
+
+    /// Sets VX to VX and VY. (bitwise AND operation)
+    fn op_and(&mut self, vx: u8, vy: u8) {
+        self.registers[vx as usize] &= self.registers[vy as usize];
+    }
+
+    /// Sets VX to VX xor VY.
+    fn op_xor(&mut self, vx: u8, vy: u8) {
+        self.registers[vx as usize] ^= self.registers[vy as usize];
+    }
+
+    /// Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there is not.
+    fn op_add(&mut self, vx: u8, vy: u8) {
+        let x = self.registers[vx as usize];
+        let y = self.registers[vy as usize];
+
+        let result = x.overflowing_add(y);
+
+        self.registers[vx as usize] = result.0;
+        self.registers[0x0F] = if result.1 { 1 } else { 0 };
+    }
+
+    /// VY is subtracted from VX. VF is set to 0 when there's a borrow, and 1 when there is not.
+    fn op_sub(&mut self, vx: u8, vy: u8) {
+        let x = self.registers[vx as usize];
+        let y = self.registers[vy as usize];
+
+        let result = x.overflowing_sub(y);
+
+        self.registers[vx as usize] = result.0;
+        self.registers[0x0F] = if result.1 { 1 } else { 0 };
+    }
+
+    /// Stores the least significant bit of VX in VF and then shifts VX to the right by 1.
+    fn op_shift_right(&mut self, vx: u8) {
+        let vf = 0x0F;
+
+        self.registers[vf as usize] = self.registers[vx as usize] & 1;
+        self.registers[vx as usize] >>= 1;
+    }
+
+    /// Sets VX to VY minus VX. VF is set to 0 when there's a borrow, and 1 when there is not.
+    fn op_sub_assign(&mut self, vx: u8, vy: u8) {
+        let x = self.registers[vx as usize];
+        let y = self.registers[vy as usize];
+
+        let result = y.overflowing_sub(x);
+
+        self.registers[vx as usize] = result.0;
+        self.registers[0x0F] = if result.1 { 1 } else { 0 };
+    }
+
+    /// Stores the most significant bit of VX in VF and then shifts VX to the left by 1.
+    fn op_shift_left(&mut self, vx: u8) {
+        let vf = 0x0F;
+
+        self.registers[vf as usize] = self.registers[vx as usize] & (1 << 7);
+        self.registers[vx as usize] <<= 1;
+    }
+
+    /// Sets I to the address NNN.
+    fn set_address_to(&mut self, address: u16) {
+        self.address = address;
+    }
+
+    /// Jumps to the address NNN plus V0, or with the `jump_with_vx` quirk to XNN plus VX (X being the first digit of the address).
+    /// The destination wraps around the memory.
+    fn jump_add(&mut self, address: u16) {
+        let register = if self.quirks.jump_with_vx {
+            (address >> 8) as usize
+        } else {
+            0
+        };
+        let offset = self.registers[register] as u16;
+
+        self.program_counter = (address + offset) % self.memory.len() as u16;
+    }
+
+    /// Sets VX to the result of a bitwise and operation on a random number (Typically: 0 to 255) and NN.
+    fn bitwise_and_with_random(&mut self, vx: u8, value: u8) {
+        self.registers[vx as usize] = self.rng.gen_range(0..=255) & value;
+    }
+
+    /// A key press is awaited, and then stored in VX (blocking operation, all instruction halted until next key event).
+    /// Like on the COSMAC VIP, the key is stored once it's released.
+    fn get_key_press(&mut self, vx: u8) {
+        if let Some(key) = self.released_key.take() {
+            self.registers[vx as usize] = key;
+            self.waiting_for_keypress = false;
+            return;
+        }
+
+        // This is a blocking operation, but since the frontend runs in the same thread,
+        //  we'll repeat the same instruction until we get a key press.
+        self.program_counter -= 2;
+        self.waiting_for_keypress = true;
+    }
+
+    /// Skips the next instruction if the key stored in VX is not pressed. Only the low digit of VX is the key.
+    fn skip_if_not_pressed(&mut self, vx: u8) {
+        let key = self.registers[vx as usize] & 0x0F;
+
+        if !self.keyboard[key as usize] {
+            self.program_counter += 2;
+        }
+    }
+
+    /// Skips the next instruction if the key stored in VX is pressed. Only the low digit of VX is the key.
+    fn skip_if_pressed(&mut self, vx: u8) {
+        let key = self.registers[vx as usize] & 0x0F;
+
+        if self.keyboard[key as usize] {
+            self.program_counter += 2;
+        }
+    }
+
+    /// Sets VX to the value of the delay timer.
+    fn set_variable_to_delay_timer(&mut self, vx: u8) {
+        self.registers[vx as usize] = self.delay_timer;
+    }
+
+    /// Adds VX to I. VF is not affected. I wraps around the memory.
+    fn add_variable_to_address(&mut self, vx: u8) {
+        let value = self.registers[vx as usize];
+
+        self.address = self.address.wrapping_add(value as u16) % self.memory.len() as u16;
+    }
+
+    /// Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels and a height of N pixels.
+    /// Each row of 8 pixels is read as bit-coded starting from memory location I; I value does not change after the execution of this instruction.
+    /// VF is set to 1 if any screen pixels are flipped from set to unset when the sprite is drawn, and to 0 if that does not happen.
+    ///
+    /// In hi-res mode a height of 0 draws a 16x16 sprite (two bytes per row), and with the `count_collided_rows` quirk
+    /// VF holds the number of rows that collided or were clipped at the bottom of the screen instead.
+    ///
+    /// With the `display_wait` quirk the instruction is repeated until the next vertical blank before drawing.
+    fn draw_sprite(&mut self, vx: u8, vy: u8, rows: u8) {
+        if self.quirks.display_wait {
+            if !self.vblank {
+                self.program_counter -= 2;
+                self.waiting_for_vblank = true;
+                return;
+            }
+            self.vblank = false;
+        }
+
+        let (width, height) = self.resolution();
+        let start_x = self.registers[vx as usize] as usize % width;
+        let start_y = self.registers[vy as usize] as usize % height;
+
+        let (sprite_width, sprite_height) = match rows {
+            0 if self.hires => (16, 16),
+            rows => (8, rows as usize),
+        };
+        let bytes_per_row = sprite_width / 8;
+
+        let mut collided_rows = 0;
+        let mut clipped_rows = 0;
+
+        for row in 0..sprite_height {
+            let mut y = start_y + row;
+
+            if y >= height {
+                if self.quirks.clip_sprites {
+                    clipped_rows += 1;
+                    continue;
+                }
+                y %= height;
+            }
+
+            let offset = self.address as usize + row * bytes_per_row;
+            let mut bits: u16 = 0;
+            for byte in 0..bytes_per_row {
+                let address = (offset + byte) % self.memory.len();
+
+                bits = (bits << 8) | self.memory[address] as u16;
+                self.coverage.mark(address, Access::Read);
+            }
+
+            let mut row_collided = false;
+
+            for column in 0..sprite_width {
+                if bits & (1 << (sprite_width - 1 - column)) == 0 {
+                    continue;
+                }
+
+                let mut x = start_x + column;
+
+                if x >= width {
+                    if self.quirks.clip_sprites {
+                        break;
+                    }
+                    x %= width;
+                }
+
+                if self.screen[x][y] == 1 {
+                    row_collided = true;
+                }
+                self.screen[x][y] ^= 1;
+                self.dirty = rect::union(self.dirty, Some(Rect::pixel(x, y)));
+            }
+
+            if row_collided {
+                collided_rows += 1;
+            }
+        }
+
+        self.registers[0x0F] = if self.hires && self.quirks.count_collided_rows {
+            collided_rows + clipped_rows
+        } else {
+            (collided_rows > 0) as u8
+        };
+    }
+
+    /// Sets I to the location of the sprite for the character in VX. Characters 0-F (in hexadecimal) are represented by a 4x5 font.
+    /// Only the low digit of VX is the character.
+    fn sets_address_to_font_sprite(&mut self, vx: u8) {
+        let character = self.registers[vx as usize] & 0x0F;
+
+        self.address = character as u16 * 5;
+    }
+
+    /// Stores the binary-coded decimal representation of VX, with the hundreds digit in memory at location in I, the tens digit at location I+1, and the ones digit at location I+2.
+    fn store_variable_as_binary(&mut self, vx: u8) {
+        let value = self.registers[vx as usize];
+
+        self.write(self.address, value / 100);
+        self.write(self.address.wrapping_add(1), (value % 100) / 10);
+        self.write(self.address.wrapping_add(2), value % 10);
+    }
+
+    /// Stores from V0 to VX (including VX) in memory, starting at address I. The offset from I is increased by 1 for each value written, but I itself is left unmodified.
+    fn dump_registers_into_memory(&mut self, vx: u8) {
+        for register in 0..(vx as u16 + 1) {
+            self.write(
+                self.address.wrapping_add(register),
+                self.registers[register as usize],
+            );
+        }
+    }
+
+    /// Fills from V0 to VX (including VX) with values from memory, starting at address I.
+    /// The offset from I is increased by 1 for each value read, but I itself is left unmodified.
+    fn load_registers_from_memory(&mut self, vx: u8) {
+        for register in 0..(vx as u16 + 1) {
+            let address = self.address.wrapping_add(register) as usize % self.memory.len();
+            self.coverage.mark(address, Access::Read);
+
+            self.registers[register as usize] = self.memory[address];
+        }
+    }
+
+    /// Sets the delay timer to VX.
+    fn set_delay_timer_to(&mut self, vx: u8) {
+        self.delay_timer = self.registers[vx as usize];
+    }
+
+    /// Sets the sound timer to VX.
+    fn set_sound_timer_to(&mut self, vx: u8) {
+        self.sound_timer = self.registers[vx as usize];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `LD I, #000` (the font), `DRW V0, V0, 5` and a jump to itself.
+    const DRAW: [u8; 6] = [0xA0, 0x00, 0xD0, 0x05, 0x12, 0x04];
+
+    #[test]
+    fn draw_waits_for_vblank_with_display_wait() {
+        let quirks = Quirks {
+            display_wait: true,
+            ..Quirks::default()
+        };
+        let mut emulator = Emulator::new(&DRAW, quirks, 0);
+
+        emulator.run().unwrap();
+        emulator.run().unwrap();
+        assert!(emulator.waiting_for_vblank);
+        assert_eq!(emulator.program_counter, 0x202);
+        assert_eq!(emulator.screen[0][0], 0);
+
+        emulator.vertical_blank();
+        emulator.run().unwrap();
+        assert!(!emulator.waiting_for_vblank);
+        assert_eq!(emulator.program_counter, 0x204);
+        assert_eq!(emulator.screen[0][0], 1);
+    }
+
+    #[test]
+    fn draw_does_not_wait_without_display_wait() {
+        for quirks in [Quirks::default(), Quirks::schip()] {
+            let mut emulator = Emulator::new(&DRAW, quirks, 0);
+
+            emulator.run().unwrap();
+            emulator.run().unwrap();
+            assert!(!emulator.waiting_for_vblank);
+            assert_eq!(emulator.program_counter, 0x204);
+            assert_eq!(emulator.screen[0][0], 1);
+        }
+    }
+
+    /// Runs `count` instructions, which must all be supported.
+    fn run(emulator: &mut Emulator, count: usize) {
+        for _ in 0..count {
+            emulator.run().unwrap();
+        }
+    }
+
+    #[test]
+    fn store_registers_decodes_what_it_wrote() {
+        // `LD V0, #65`, `LD V1, #AB`, `LD I, #208` and `LD [I], V1`, which writes `LD V5, #AB` over the `SYS #000` after it
+        let program = [0x60, 0x65, 0x61, 0xAB, 0xA2, 0x08, 0xF1, 0x55];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+
+        run(&mut emulator, 5);
+        assert_eq!(emulator.registers[5], 0xAB);
+    }
+
+    #[test]
+    fn poke_decodes_what_it_wrote() {
+        // `LD V5, #00`, then `SYS #000`
+        let mut emulator = Emulator::new(&[0x65, 0x00], Quirks::default(), 0);
+
+        // The second byte of an instruction, and a whole one
+        emulator.poke(0x201, 0xAB);
+        emulator.poke(0x202, 0x66);
+        emulator.poke(0x203, 0xCD);
+
+        run(&mut emulator, 2);
+        assert_eq!(emulator.registers[5], 0xAB);
+        assert_eq!(emulator.registers[6], 0xCD);
+    }
+
+    #[test]
+    fn load_and_reset_decode_the_program() {
+        // `LD V5, #01`, poked into `LD V6, #01`
+        let mut emulator = Emulator::new(&[0x65, 0x01], Quirks::default(), 0);
+        emulator.poke(0x200, 0x66);
+
+        emulator.reset();
+        run(&mut emulator, 1);
+        assert_eq!(emulator.registers[5], 0x01);
+        assert_eq!(emulator.registers[6], 0x00);
+
+        // `LD V7, #02`
+        emulator.load(&[0x67, 0x02], PROGRAM_START, Quirks::default());
+        run(&mut emulator, 1);
+        assert_eq!(emulator.registers[7], 0x02);
+    }
+
+    /// Where `JP V0, address` jumps to, with V0 = #FF, V2 = #10 and VF = #02.
+    fn jump_with_offset(address: u16, jump_with_vx: bool) -> u16 {
+        let [high, low] = (0xB000 | address).to_be_bytes();
+        let program = [0x60, 0xFF, 0x62, 0x10, 0x6F, 0x02, high, low];
+        let quirks = Quirks {
+            jump_with_vx,
+            ..Quirks::default()
+        };
+        let mut emulator = Emulator::new(&program, quirks, 0);
+
+        run(&mut emulator, 4);
+        emulator.program_counter
+    }
+
+    #[test]
+    fn jump_with_offset_adds_v0() {
+        assert_eq!(jump_with_offset(0x2FF, false), 0x3FE);
+        // Past the end of the memory, back to the start
+        assert_eq!(jump_with_offset(0xFFF, false), 0x0FE);
+    }
+
+    #[test]
+    fn jump_with_vx_adds_the_register_of_the_high_digit() {
+        // `B2FF` adds V2, `BFFF` adds VF
+        assert_eq!(jump_with_offset(0x2FF, true), 0x30F);
+        assert_eq!(jump_with_offset(0xFFF, true), 0x001);
+    }
+
+    #[test]
+    fn memory_wraps_around_after_i() {
+        // `LD I, #FFE`, `LD V0, #FF`, `LD B, V0` (2, 5 and 5), `LD V2, [I]`,
+        // `LD V3, #20`, `LD V4, #12`, `LD [I], V4`, `ADD I, V3`
+        let program = [
+            0xAF, 0xFE, 0x60, 0xFF, 0xF0, 0x33, 0xF2, 0x65, 0x63, 0x20, 0x64, 0x12, 0xF4, 0x55,
+            0xF3, 0x1E,
+        ];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+
+        run(&mut emulator, 4);
+        assert_eq!(emulator.memory()[0xFFE..], [2, 5]);
+        assert_eq!(emulator.memory()[0], 5);
+        assert_eq!(emulator.registers[..3], [2, 5, 5]);
+
+        run(&mut emulator, 4);
+        assert_eq!(emulator.memory()[0xFFE..], [2, 5]);
+        assert_eq!(emulator.memory()[..3], [5, 0x20, 0x12]);
+        assert_eq!(emulator.address, 0x01E);
+    }
+
+    #[test]
+    fn stack_overflow() {
+        // `CALL #200`, forever
+        let mut emulator = Emulator::new(&[0x22, 0x00], Quirks::default(), 0);
+
+        run(&mut emulator, 16);
+        assert_eq!(emulator.stack_pointer, 16);
+
+        assert_eq!(emulator.run(), Err(String::from("Stack overflow at 200")));
+        assert_eq!(emulator.program_counter, 0x200);
+        assert_eq!(emulator.stack_pointer, 16);
+    }
+
+    #[test]
+    fn return_with_an_empty_stack() {
+        // `CALL #204`, `JP #204` and `RET`: the `RET` runs again after the return
+        let program = [0x22, 0x04, 0x12, 0x04, 0x00, 0xEE];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+
+        run(&mut emulator, 3);
+        assert_eq!(emulator.program_counter, 0x204);
+        assert_eq!(emulator.stack_pointer, 0);
+
+        assert_eq!(
+            emulator.run(),
+            Err(String::from("Return with an empty stack at 204"))
+        );
+        assert_eq!(emulator.program_counter, 0x204);
+    }
+
+    #[test]
+    fn reset_draws_the_same_random_numbers() {
+        // `RND V0, #FF`, `RND V1, #FF`, `RND V2, #FF`
+        let program = [0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 42);
+
+        run(&mut emulator, 3);
+        let first = emulator.registers;
+
+        emulator.reset();
+        run(&mut emulator, 3);
+        assert_eq!(emulator.registers, first);
+
+        emulator.load(&program, PROGRAM_START, Quirks::default());
+        run(&mut emulator, 3);
+        assert_ne!(emulator.registers, first);
+    }
+
+    #[test]
+    fn draw_marks_the_sprite_dirty() {
+        // `LD V0, 10`, `LD V1, 5`, `LD I, #000` (the 0 of the font), `DRW V0, V1, 5` and `CLS`
+        let program = [0x60, 0x0A, 0x61, 0x05, 0xA0, 0x00, 0xD0, 0x15, 0x00, 0xE0];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+
+        run(&mut emulator, 3);
+        assert_eq!(emulator.take_dirty(), None);
+
+        run(&mut emulator, 1);
+        assert_eq!(emulator.take_dirty(), Some(Rect::new(10, 5, 4, 5)));
+        assert_eq!(emulator.take_dirty(), None);
+
+        run(&mut emulator, 1);
+        assert_eq!(
+            emulator.take_dirty(),
+            Some(Rect::new(0, 0, SCHIP_WIDTH, SCHIP_HEIGHT))
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use std::str::FromStr;
+
+use crate::{
+    rect::{self, Rect},
+    SCHIP_HEIGHT, SCHIP_WIDTH,
+};
+
+/// How the pixels of the emulated screen are turned into what's shown in the window.
+/// CHIP-8 programs move sprites by erasing and redrawing them, which flickers; these filters hide some of it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Filter {
+    /// Show the screen as it is.
+    #[default]
+    None,
+
+    /// Average the last two frames, so a sprite that is only visible every other frame is shown at half brightness.
+    Blend,
+
+    /// Turned off pixels fade out instead of disappearing, like the phosphor of a CRT.
+    /// The value is how much of the brightness is kept every frame (0.0 to 1.0).
+    Decay(f32),
+
+    /// Only show a new frame when a pixel was turned on, so the frames where a sprite is erased before being redrawn are skipped.
+    /// The value is the amount of frames after which the screen is shown anyway.
+    SetOnly(u32),
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    /// Parses `none`, `blend`, `decay[:FACTOR]` or `set-only[:FRAMES]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (s, None),
+        };
+
+        match (name, value) {
+            ("none", None) => Ok(Self::None),
+            ("blend", None) => Ok(Self::Blend),
+            ("decay", None) => Ok(Self::Decay(0.7)),
+            ("decay", Some(value)) => match value.parse::<f32>() {
+                Ok(factor) if (0.0..=1.0).contains(&factor) => Ok(Self::Decay(factor)),
+                _ => Err(format!(
+                    "Invalid decay factor '{value}', expected a number between 0 and 1."
+                )),
+            },
+            ("set-only", None) => Ok(Self::SetOnly(3)),
+            ("set-only", Some(value)) => match value.parse::<u32>() {
+                Ok(frames) => Ok(Self::SetOnly(frames)),
+                _ => Err(format!("Invalid amount of frames '{value}'.")),
+            },
+            _ => Err(format!(
+                "Unknown filter '{s}', expected none, blend, decay or set-only."
+            )),
+        }
+    }
+}
+
+/// Keeps the brightness (0 to 255) of every pixel as it should be displayed, updated once per frame.
+pub struct ScreenFilter {
+    filter: Filter,
+
+    intensity: [[u8; SCHIP_HEIGHT]; SCHIP_WIDTH],
+    previous: [[u8; SCHIP_HEIGHT]; SCHIP_WIDTH],
+
+    /// What changed in the previous frame (`Blend`), is still fading out (`Decay`) or hasn't been shown yet (`SetOnly`).
+    pending: Option<Rect>,
+    frames_held: u32,
+}
+
+impl ScreenFilter {
+    pub fn new(filter: Filter) -> Self {
+        Self {
+            filter,
+
+            intensity: [[0u8; SCHIP_HEIGHT]; SCHIP_WIDTH],
+            previous: [[0u8; SCHIP_HEIGHT]; SCHIP_WIDTH],
+
+            pending: None,
+            frames_held: 0,
+        }
+    }
+
+    /// Feeds the screen at the end of a frame, along with the part of it that changed during the frame.
+    /// Returns the part of the screen whose brightness may have changed.
+    pub fn update(
+        &mut self,
+        screen: &[[u8; SCHIP_HEIGHT]; SCHIP_WIDTH],
+        dirty: Option<Rect>,
+    ) -> Option<Rect> {
+        match self.filter {
+            Filter::None => {
+                if let Some(region) = dirty {
+                    self.show(screen, region);
+                }
+                dirty
+            }
+            Filter::Blend => {
+                let region = rect::union(dirty, self.pending);
+
+                if let Some(region) = region {
+                    let columns = self.intensity[region.x..region.right()]
+                        .iter_mut()
+                        .zip(&screen[region.x..region.right()])
+                        .zip(&mut self.previous[region.x..region.right()]);
+
+                    for ((intensity, pixels), previous) in columns {
+                        let rows = intensity[region.y..region.bottom()]
+                            .iter_mut()
+                            .zip(&pixels[region.y..region.bottom()])
+                            .zip(&mut previous[region.y..region.bottom()]);
+
+                        for ((intensity, pixel), previous) in rows {
+                            *intensity = ((*pixel + *previous) as u16 * 255 / 2) as u8;
+                            *previous = *pixel;
+                        }
+                    }
+                }
+
+                // The pixels that changed now will change again next frame, once they are no longer blended with this one
+                self.pending = dirty;
+                region
+            }
+            Filter::Decay(factor) => {
+                let region = rect::union(dirty, self.pending);
+                self.pending = None;
+
+                if let Some(region) = region {
+                    let columns = self.intensity[region.x..region.right()]
+                        .iter_mut()
+                        .zip(&screen[region.x..region.right()]);
+
+                    for (x, (intensity, pixels)) in (region.x..).zip(columns) {
+                        let rows = intensity[region.y..region.bottom()]
+                            .iter_mut()
+                            .zip(&pixels[region.y..region.bottom()]);
+
+                        for (y, (intensity, pixel)) in (region.y..).zip(rows) {
+                            if *pixel == 1 {
+                                *intensity = 255;
+                            } else if *intensity > 0 {
+                                *intensity = (*intensity as f32 * factor) as u8;
+                                self.pending = rect::union(self.pending, Some(Rect::pixel(x, y)));
+                            }
+                        }
+                    }
+                }
+
+                region
+            }
+            Filter::SetOnly(max_frames) => {
+                self.pending = rect::union(self.pending, dirty);
+
+                let region = self.pending?;
+
+                let pixels = (region.x..region.right())
+                    .flat_map(|x| (region.y..region.bottom()).map(move |y| (x, y)))
+                    .map(|(x, y)| (screen[x][y] == 1, self.intensity[x][y] > 0));
+
+                let mut pixel_set = false;
+                let mut changed = false;
+                for (on, shown) in pixels {
+                    pixel_set |= on && !shown;
+                    changed |= on != shown;
+                }
+
+                if !changed {
+                    // Erased and drawn back in the same frame
+                    self.frames_held = 0;
+                    self.pending = None;
+                    None
+                } else if pixel_set || self.frames_held >= max_frames {
+                    self.frames_held = 0;
+                    self.pending = None;
+                    self.show(screen, region);
+                    Some(region)
+                } else {
+                    self.frames_held += 1;
+                    None
+                }
+            }
+        }
+    }
+
+    fn show(&mut self, screen: &[[u8; SCHIP_HEIGHT]; SCHIP_WIDTH], region: Rect) {
+        let columns = self.intensity[region.x..region.right()]
+            .iter_mut()
+            .zip(&screen[region.x..region.right()]);
+
+        for (intensity, pixels) in columns {
+            let rows = intensity[region.y..region.bottom()]
+                .iter_mut()
+                .zip(&pixels[region.y..region.bottom()]);
+
+            for (intensity, pixel) in rows {
+                *intensity = if *pixel == 1 { 255 } else { 0 };
+            }
+        }
+    }
+
+    /// The brightness of a pixel, from 0 (off) to 255 (on).
+    pub fn intensity(&self, x: usize, y: usize) -> u8 {
+        self.intensity[x][y]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIXEL: Option<Rect> = Some(Rect::pixel(3, 4));
+
+    /// A screen with only the pixel of `PIXEL` on, or nothing.
+    fn screen(on: bool) -> [[u8; SCHIP_HEIGHT]; SCHIP_WIDTH] {
+        let mut screen = [[0u8; SCHIP_HEIGHT]; SCHIP_WIDTH];
+        screen[3][4] = on as u8;
+        screen
+    }
+
+    #[test]
+    fn blend_averages_two_frames() {
+        let mut filter = ScreenFilter::new(Filter::Blend);
+
+        assert_eq!(filter.update(&screen(true), PIXEL), PIXEL);
+        assert_eq!(filter.intensity(3, 4), 127);
+
+        // Unchanged, but no longer blended with the frame before
+        assert_eq!(filter.update(&screen(true), None), PIXEL);
+        assert_eq!(filter.intensity(3, 4), 255);
+
+        assert_eq!(filter.update(&screen(true), None), None);
+    }
+
+    #[test]
+    fn decay_fades_out_to_0() {
+        let mut filter = ScreenFilter::new(Filter::Decay(0.5));
+
+        filter.update(&screen(true), PIXEL);
+        assert_eq!(filter.intensity(3, 4), 255);
+
+        filter.update(&screen(false), PIXEL);
+        assert_eq!(filter.intensity(3, 4), 127);
+
+        // 63, 31, 15, 7, 3, 1 and 0
+        for _ in 0..7 {
+            assert_eq!(filter.update(&screen(false), None), PIXEL);
+        }
+        assert_eq!(filter.intensity(3, 4), 0);
+
+        assert_eq!(filter.update(&screen(false), None), PIXEL);
+        assert_eq!(filter.update(&screen(false), None), None);
+    }
+
+    #[test]
+    fn set_only_skips_the_erased_frames() {
+        let mut filter = ScreenFilter::new(Filter::SetOnly(3));
+
+        assert_eq!(filter.update(&screen(true), PIXEL), PIXEL);
+        assert_eq!(filter.intensity(3, 4), 255);
+
+        // Erased, then drawn back: never shown off
+        assert_eq!(filter.update(&screen(false), PIXEL), None);
+        assert_eq!(filter.update(&screen(true), PIXEL), None);
+        assert_eq!(filter.intensity(3, 4), 255);
+
+        // Erased for good: held for 3 frames, then shown
+        assert_eq!(filter.update(&screen(false), PIXEL), None);
+        assert_eq!(filter.update(&screen(false), None), None);
+        assert_eq!(filter.update(&screen(false), None), None);
+        assert_eq!(filter.intensity(3, 4), 255);
+        assert_eq!(filter.update(&screen(false), None), PIXEL);
+        assert_eq!(filter.intensity(3, 4), 0);
+    }
+}
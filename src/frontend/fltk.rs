@@ -0,0 +1,396 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fs,
+    path::Path,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use fltk::{
+    app::{self, event_key},
+    dialog::{self, beep, BeepType},
+    enums::{Event, Key},
+    frame::Frame,
+    prelude::*,
+    window::Window,
+};
+
+use super::{
+    cheat_command, keypad_key,
+    launcher::{self, Launcher},
+    Frontend,
+};
+use crate::{
+    cheats::Comparison,
+    filter::ScreenFilter,
+    keymap::{Button, Keymap},
+    library::Recent,
+    palette::Palette,
+    rect::Rect,
+    render, CHIP8_HEIGHT, CHIP8_WIDTH,
+};
+
+/// A resizable window, drawn by re-rasterizing the screen into an RGBA buffer.
+/// Ctrl+O opens another program, Ctrl+L shows the launcher and Ctrl+S saves the settings.
+/// P pauses, N runs the next frame while paused, Tab fast forwards, Ctrl+R restarts the program and Ctrl+Shift+R reloads it.
+/// F3 shows the counters and the quirks, F12 takes a screenshot and Shift+F12 starts or stops a recording.
+/// F5 to F9 search the memory and freeze what they find (see `cheat_command`), Alt+1 to Alt+9 toggle the cheats.
+pub struct FltkFrontend {
+    window: Window,
+    frame: Frame,
+    launcher: Launcher,
+    recent: Rc<RefCell<Recent>>,
+
+    frame_buffer: Vec<u8>,
+    frame_size: (i32, i32),
+    palette: Palette,
+    /// The lines of the on-screen display.
+    osd: Vec<String>,
+
+    events: Arc<Mutex<VecDeque<super::Event>>>,
+    keymap: Rc<Cell<Keymap>>,
+    start: Instant,
+    beeping: bool,
+}
+
+impl FltkFrontend {
+    /// A window of `scale` pixels per CHIP-8 pixel, whose launcher shows the `recent` programs.
+    pub fn new(scale: usize, recent: Recent) -> Self {
+        app::App::default();
+
+        let width = (CHIP8_WIDTH * scale) as i32;
+        let height = (CHIP8_HEIGHT * scale) as i32;
+
+        let mut window = Window::default()
+            .with_size(width, height)
+            .with_label("Rusty8");
+
+        let frame = Frame::default().size_of(&window);
+
+        window.end();
+        window.make_resizable(true);
+        window.size_range(CHIP8_WIDTH as i32, CHIP8_HEIGHT as i32, 0, 0);
+        window.show();
+
+        let events = Arc::new(Mutex::new(VecDeque::with_capacity(10)));
+        let recent = Rc::new(RefCell::new(recent));
+
+        let open = {
+            let events = events.clone();
+            let recent = recent.clone();
+            let window = window.clone();
+
+            move |path: &Path| open(path, window.clone(), &events, &recent)
+        };
+
+        let keymap = Rc::new(Cell::new(Keymap::default()));
+        let launcher = Launcher::new(recent.clone(), open.clone());
+        handle_events(
+            &mut window,
+            events.clone(),
+            keymap.clone(),
+            launcher.clone(),
+            open,
+        );
+
+        Self {
+            window,
+            frame,
+            launcher,
+            recent,
+
+            frame_buffer: Vec::new(),
+            frame_size: (0, 0),
+            palette: Palette::default(),
+            osd: Vec::new(),
+
+            events,
+            keymap,
+            start: Instant::now(),
+            beeping: false,
+        }
+    }
+
+    /// Shows the name of the program that was loaded from `path` and remembers it.
+    pub fn set_rom(&mut self, path: &Path) {
+        set_title(&mut self.window, path);
+        let _ = self.recent.borrow_mut().add(path);
+    }
+
+    /// Shows the launcher, to pick a program.
+    pub fn show_launcher(&mut self) {
+        self.launcher.show();
+    }
+}
+
+fn set_title(window: &mut Window, path: &Path) {
+    match path.file_name() {
+        Some(name) => window.set_label(&format!("Rusty8 - {}", name.to_string_lossy())),
+        None => window.set_label("Rusty8"),
+    }
+}
+
+/// Reads the program at `path` and asks for the emulator to switch to it.
+fn open(
+    path: &Path,
+    mut window: Window,
+    events: &Mutex<VecDeque<super::Event>>,
+    recent: &RefCell<Recent>,
+) {
+    let program = match fs::read(path) {
+        Ok(program) => program,
+        Err(error) => {
+            dialog::alert_default(&format!("Could not open {}: {error}", path.display()));
+            return;
+        }
+    };
+
+    set_title(&mut window, path);
+    let _ = recent.borrow_mut().add(path);
+
+    events
+        .lock()
+        .expect("Could not get a lock on the keyboard events")
+        .push_back(super::Event::Load(program));
+}
+
+impl Frontend for FltkFrontend {
+    fn present(
+        &mut self,
+        screen: &ScreenFilter,
+        resolution: (usize, usize),
+        mut region: Option<Rect>,
+    ) {
+        // The window was resized (or just shown): draw everything
+        if self.frame_size != (self.frame.w(), self.frame.h()) {
+            self.frame_size = (self.frame.w(), self.frame.h());
+            self.frame_buffer = vec![0; (self.frame.w() * self.frame.h() * 4) as usize];
+            region = None;
+        }
+
+        render::rasterize(
+            &mut self.frame_buffer,
+            self.frame.w() as usize,
+            self.frame.h() as usize,
+            screen,
+            resolution,
+            region,
+            &self.palette,
+        );
+        render::draw_osd(
+            &mut self.frame_buffer,
+            self.frame.w() as usize,
+            self.frame.h() as usize,
+            &self.osd,
+        );
+
+        fltk::draw::draw_rgba(&mut self.frame, &self.frame_buffer).unwrap();
+        self.window.redraw();
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+
+        // Redraw the bars around the screen too
+        self.frame_size = (0, 0);
+    }
+
+    fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap.set(keymap);
+    }
+
+    fn set_osd(&mut self, lines: &[String]) {
+        self.osd = lines.to_vec();
+    }
+
+    fn poll_input(&mut self) -> Vec<super::Event> {
+        let mut events: Vec<_> = self
+            .events
+            .lock()
+            .expect("Could not get a lock on the keyboard events")
+            .drain(..)
+            .collect();
+
+        if !self.window.shown() {
+            events.push(super::Event::Quit);
+        }
+
+        events
+    }
+
+    /// FLTK can't play sounds, so this beeps once every time the sound timer is set.
+    fn play_audio(&mut self, playing: bool) {
+        if playing && !self.beeping {
+            beep(BeepType::Default);
+        }
+
+        self.beeping = playing;
+    }
+
+    fn time(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn wait(&mut self, timeout: Duration) {
+        // Also processes the window events, and returns early if one happened.
+        let _ = app::wait_for(timeout.as_secs_f64());
+    }
+}
+
+/// The button of the keymap on this key of the keyboard.
+fn button(key: Key) -> Option<Button> {
+    if key == Key::Up {
+        Some(Button::Up)
+    } else if key == Key::Down {
+        Some(Button::Down)
+    } else if key == Key::Left {
+        Some(Button::Left)
+    } else if key == Key::Right {
+        Some(Button::Right)
+    } else if key == Key::from_char(' ') {
+        Some(Button::A)
+    } else if key == Key::Enter {
+        Some(Button::B)
+    } else {
+        None
+    }
+}
+
+fn handle_events(
+    window: &mut Window,
+    events: Arc<Mutex<VecDeque<super::Event>>>,
+    keymap: Rc<Cell<Keymap>>,
+    mut launcher: Launcher,
+    open: impl Fn(&Path) + 'static,
+) {
+    window.handle(move |window, event| {
+        if event != Event::KeyDown && event != Event::KeyUp {
+            return false;
+        }
+
+        let key = event_key();
+        let ascii_key = key.bits() as u8 as char;
+
+        if event == Event::KeyDown && app::is_event_ctrl() {
+            match ascii_key {
+                'o' => {
+                    if let Some(path) = launcher::choose_file(launcher.directory().as_deref()) {
+                        open(&path);
+                    }
+                }
+                'l' => launcher.show(),
+                's' => events
+                    .lock()
+                    .expect("Could not get a lock on the keyboard events")
+                    .push_back(super::Event::SaveSettings),
+                'r' => events
+                    .lock()
+                    .expect("Could not get a lock on the keyboard events")
+                    .push_back(if app::is_event_shift() {
+                        super::Event::Reset
+                    } else {
+                        super::Event::SoftReset
+                    }),
+                _ => return false,
+            }
+
+            return true;
+        }
+
+        // The dialog runs the event loop, and the frames it runs take the events: they can't be held while it's shown
+        if event == Event::KeyDown && key == Key::F8 {
+            // Cancelling searches nothing
+            if let Some(text) = dialog::input_default("Search the bytes equal to (hex):", "") {
+                match u8::from_str_radix(text.trim(), 16) {
+                    Ok(value) => events
+                        .lock()
+                        .expect("Could not get a lock on the keyboard events")
+                        .push_back(super::Event::NarrowSearch(Comparison::Equal(value))),
+                    Err(_) => beep(BeepType::Error),
+                }
+            }
+
+            return true;
+        }
+
+        let mut events = events
+            .lock()
+            .expect("Could not get a lock on the keyboard events");
+
+        let keypad = match button(key) {
+            Some(button) => keymap.get().key(button),
+            None => keypad_key(ascii_key),
+        };
+
+        match event {
+            Event::KeyDown => {
+                if key == Key::Escape {
+                    app::quit();
+                }
+
+                if key == Key::F11 {
+                    window.fullscreen(!window.fullscreen_active());
+                    return true;
+                }
+
+                if key == Key::F3 {
+                    events.push_back(super::Event::ToggleInfo);
+                    return true;
+                }
+
+                if key == Key::F12 {
+                    events.push_back(if app::is_event_shift() {
+                        super::Event::ToggleRecording
+                    } else {
+                        super::Event::Screenshot
+                    });
+                    return true;
+                }
+
+                if Key::is_fn_key(key) {
+                    let number = (key.bits() - Key::F1.bits() + 1) as u8;
+
+                    if let Some(command) = cheat_command(number, app::is_event_shift()) {
+                        events.push_back(command);
+                        return true;
+                    }
+                }
+
+                if app::is_event_alt() {
+                    if let Some(digit) = ascii_key.to_digit(10).filter(|digit| *digit > 0) {
+                        events.push_back(super::Event::ToggleCheat(digit as usize - 1));
+                        return true;
+                    }
+                }
+
+                let command = match ascii_key {
+                    'p' => Some(super::Event::TogglePause),
+                    'n' => Some(super::Event::Step),
+                    _ if key == Key::Tab => Some(super::Event::ToggleFastForward),
+                    _ => None,
+                };
+
+                if let Some(command) = command {
+                    events.push_back(command);
+                    return true;
+                }
+
+                if let Some(key) = keypad {
+                    events.push_back(super::Event::KeyDown(key));
+                }
+
+                true
+            }
+            Event::KeyUp => {
+                if let Some(key) = keypad {
+                    events.push_back(super::Event::KeyUp(key));
+                }
+
+                true
+            }
+            _ => unreachable!(),
+        }
+    });
+}
@@ -0,0 +1,204 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use fltk::{
+    app,
+    browser::HoldBrowser,
+    button::Button,
+    dialog::{FileDialog, FileDialogType},
+    prelude::*,
+    window::Window,
+};
+
+use crate::library::{self, Recent};
+
+/// Asks for a program with the native file dialog. `None` if it was cancelled.
+pub fn choose_file(directory: Option<&Path>) -> Option<PathBuf> {
+    let mut dialog = FileDialog::new(FileDialogType::BrowseFile);
+    dialog.set_title("Open a program");
+    dialog.set_filter("CHIP-8 programs\t*.{ch8,sc8,xo8}");
+
+    if let Some(directory) = directory {
+        let _ = dialog.set_directory(&directory);
+    }
+
+    dialog.show();
+
+    let path = dialog.filename();
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+fn choose_directory() -> Option<PathBuf> {
+    let mut dialog = FileDialog::new(FileDialogType::BrowseDir);
+    dialog.set_title("Choose a folder of programs");
+    dialog.show();
+
+    let path = dialog.filename();
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// The lines of the browser: the recent programs, then the ones found in the chosen folder.
+#[derive(Clone)]
+struct List {
+    browser: HoldBrowser,
+    /// The program of every line of the browser, `None` for the headings.
+    lines: Rc<RefCell<Vec<Option<PathBuf>>>>,
+    directory: Rc<RefCell<Option<PathBuf>>>,
+    recent: Rc<RefCell<Recent>>,
+}
+
+impl List {
+    fn refresh(&mut self) {
+        let mut lines = self.lines.borrow_mut();
+
+        self.browser.clear();
+        lines.clear();
+
+        let mut add = |text: String, path: Option<PathBuf>| {
+            self.browser.add(&text);
+            lines.push(path);
+        };
+
+        let recent = self.recent.borrow();
+        if !recent.roms().is_empty() {
+            add(String::from("@bRecent"), None);
+
+            for rom in recent.roms() {
+                // "@." stops the browser from reading formatting characters in the name
+                add(format!("@.{}", rom.display()), Some(rom.clone()));
+            }
+        }
+
+        if let Some(directory) = self.directory.borrow().as_ref() {
+            add(format!("@b@.{}", directory.display()), None);
+
+            for rom in library::scan(directory) {
+                let name = rom
+                    .strip_prefix(directory)
+                    .unwrap_or(&rom)
+                    .display()
+                    .to_string();
+                add(format!("@.{name}"), Some(rom));
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<PathBuf> {
+        let line = self.browser.value();
+        if line < 1 {
+            return None;
+        }
+
+        self.lines
+            .borrow()
+            .get(line as usize - 1)
+            .cloned()
+            .flatten()
+    }
+}
+
+/// A window to pick the next program from the recent ones, a folder, or a file dialog.
+#[derive(Clone)]
+pub struct Launcher {
+    window: Window,
+    list: List,
+}
+
+impl Launcher {
+    /// `open` is called with the chosen program.
+    pub fn new(recent: Rc<RefCell<Recent>>, open: impl Fn(&Path) + 'static) -> Self {
+        let open = Rc::new(open);
+
+        // Start in the folder of the last program
+        let directory = recent
+            .borrow()
+            .roms()
+            .first()
+            .and_then(|rom| rom.parent())
+            .map(Path::to_path_buf);
+
+        let mut window = Window::default()
+            .with_size(480, 360)
+            .with_label("Rusty8 - Open a program");
+
+        let browser = HoldBrowser::new(10, 10, 460, 300, None);
+        let mut open_file = Button::new(10, 320, 110, 30, "Open file...");
+        let mut choose_folder = Button::new(130, 320, 130, 30, "Choose folder...");
+        let mut launch = Button::new(360, 320, 110, 30, "Launch");
+
+        window.end();
+        window.make_resizable(true);
+
+        let list = List {
+            browser,
+            lines: Rc::new(RefCell::new(Vec::new())),
+            directory: Rc::new(RefCell::new(directory)),
+            recent,
+        };
+
+        let mut launch_selected = {
+            let list = list.clone();
+            let mut window = window.clone();
+            let open = open.clone();
+
+            move || {
+                if let Some(rom) = list.selected() {
+                    window.hide();
+                    open(&rom);
+                }
+            }
+        };
+
+        {
+            let mut launch_selected = launch_selected.clone();
+            list.browser.clone().set_callback(move |_| {
+                if app::event_clicks() {
+                    launch_selected();
+                }
+            });
+        }
+
+        launch.set_callback(move |_| launch_selected());
+
+        {
+            let list = list.clone();
+            let mut window = window.clone();
+
+            open_file.set_callback(move |_| {
+                let directory = list.directory.borrow().clone();
+
+                if let Some(rom) = choose_file(directory.as_deref()) {
+                    window.hide();
+                    open(&rom);
+                }
+            });
+        }
+
+        {
+            let mut list = list.clone();
+
+            choose_folder.set_callback(move |_| {
+                if let Some(directory) = choose_directory() {
+                    *list.directory.borrow_mut() = Some(directory);
+                    list.refresh();
+                }
+            });
+        }
+
+        Self { window, list }
+    }
+
+    /// Shows the window with an up-to-date list.
+    pub fn show(&mut self) {
+        self.list.refresh();
+        self.window.show();
+    }
+
+    /// The folder shown in the launcher, where the file dialog starts too.
+    pub fn directory(&self) -> Option<PathBuf> {
+        self.list.directory.borrow().clone()
+    }
+}
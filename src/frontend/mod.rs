@@ -0,0 +1,535 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fltk;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod launcher;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    capture::{self, Recorder},
+    cheats::{self, Cheat, Cheats, Code, Comparison, Search},
+    database::Settings,
+    filter::ScreenFilter,
+    instruction::Instruction,
+    keymap::Keymap,
+    osd::Osd,
+    palette::Palette,
+    rect,
+    rect::Rect,
+    scheduler::{self, Scheduler},
+    Emulator,
+};
+
+/// Something that happened in the frontend since it was last polled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A key of the hex keypad (0x0 to 0xF) was pressed.
+    KeyDown(u8),
+    /// A key of the hex keypad (0x0 to 0xF) was released.
+    KeyUp(u8),
+    /// The user picked another program to run.
+    Load(Vec<u8>),
+    /// The user asked to make the settings of the running program the defaults.
+    SaveSettings,
+    /// The user asked to pause, or to resume.
+    TogglePause,
+    /// The user asked to run a single frame, while paused.
+    Step,
+    /// The user asked to run faster (see `Scheduler::set_fast_forward`), or at the normal speed again.
+    ToggleFastForward,
+    /// The user asked to show or hide the counters and the quirks on the screen.
+    ToggleInfo,
+    /// The user asked for a screenshot.
+    Screenshot,
+    /// The user asked to start recording the screen, or to stop.
+    ToggleRecording,
+    /// The user asked to reload the program and start over.
+    Reset,
+    /// The user asked to restart the program, keeping the memory.
+    SoftReset,
+    /// The user changed a byte of the memory in the debugger. Only applied while paused.
+    Poke { address: u16, value: u8 },
+    /// The user asked to search the memory again, starting with every byte.
+    NewSearch,
+    /// The user asked to keep the bytes of the search that compare with their previous value.
+    NarrowSearch(Comparison),
+    /// The user asked to freeze the bytes left in the search at their current values.
+    FreezeCandidates,
+    /// The user asked to enable or disable a cheat, by index.
+    ToggleCheat(usize),
+    /// The whole screen must be drawn again, e.g. the terminal was resized and cleared.
+    Redraw,
+    /// The user asked to close the emulator.
+    Quit,
+}
+
+/// A snapshot of the CPU, for frontends that show it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Registers {
+    pub v: [u8; 16],
+    pub i: u16,
+    pub pc: u16,
+    pub sp: u8,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    /// The instruction at the program counter, about to run.
+    pub instruction: Instruction,
+}
+
+impl Registers {
+    pub fn of(emulator: &Emulator) -> Self {
+        Self {
+            v: emulator.registers,
+            i: emulator.address,
+            pc: emulator.program_counter,
+            sp: emulator.stack_pointer,
+            delay_timer: emulator.delay_timer,
+            sound_timer: emulator.sound_timer,
+            instruction: emulator.next_instruction(),
+        }
+    }
+}
+
+/// Everything the emulator needs from the outside world: a screen, a keypad, a speaker and a clock.
+pub trait Frontend {
+    /// Shows the screen. Only `region` changed since the last call, or everything if there's none.
+    fn present(&mut self, screen: &ScreenFilter, resolution: (usize, usize), region: Option<Rect>);
+
+    /// Called after every frame, for frontends that show the state of the CPU.
+    fn show_registers(&mut self, _registers: &Registers) {}
+
+    /// Called after every frame and every change of the memory, after `show_registers`, for frontends that show the memory.
+    /// `program` is where the program was loaded, and `written` are the addresses it wrote to since the last call.
+    fn show_memory(&mut self, _memory: &[u8], _program: Range<u16>, _written: &[u16]) {}
+
+    /// Called when the cheats or the search of the memory change, for frontends that show them.
+    fn show_cheats(&mut self, _cheats: &[Cheat], _search: Option<&Search>) {}
+
+    /// The colors of the program, for frontends that can show them.
+    fn set_palette(&mut self, _palette: Palette) {}
+
+    /// The keys of the keypad that the arrow keys, Space and Enter press.
+    fn set_keymap(&mut self, _keymap: Keymap) {}
+
+    /// The lines of the on-screen display (see `osd::Overlay`), drawn over the screen from the next call to `present`.
+    fn set_osd(&mut self, _lines: &[String]) {}
+
+    /// The events that happened since the last call.
+    fn poll_input(&mut self) -> Vec<Event>;
+
+    /// An error that stopped the frontend, e.g. the terminal can't be written to anymore. `run` returns it.
+    fn take_error(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Called every frame with whether the sound timer is active.
+    fn play_audio(&mut self, playing: bool);
+
+    /// The time elapsed since the frontend started.
+    fn time(&self) -> Duration;
+
+    /// Waits until the next frame is due, handling events in the meantime if needed.
+    fn wait(&mut self, timeout: Duration) {
+        std::thread::sleep(timeout);
+    }
+}
+
+/// Maps the keyboard to the hex keypad.
+pub fn keypad_key(key: char) -> Option<u8> {
+    match key.to_ascii_uppercase() {
+        /*  (Hex)        (KBD)
+           1 2 3 C  =>  1 2 3 4
+           4 5 6 D  =>  Q W E R
+           7 8 9 E  =>  A S D F
+           A 0 B F  =>  Z X C V
+        */
+        '1' => Some(0x01),
+        '2' => Some(0x02),
+        '3' => Some(0x03),
+        '4' => Some(0x0C),
+
+        'Q' => Some(0x04),
+        'W' => Some(0x05),
+        'E' => Some(0x06),
+        'R' => Some(0x0D),
+
+        'A' => Some(0x07),
+        'S' => Some(0x08),
+        'D' => Some(0x09),
+        'F' => Some(0x0E),
+
+        'Z' => Some(0x0A),
+        'X' => Some(0x00),
+        'C' => Some(0x0B),
+        'V' => Some(0x0F),
+        _ => None,
+    }
+}
+
+/// The commands of the cheats on the function keys, the same in every frontend: F5 starts a search of the memory,
+/// F6 keeps the bytes that changed (Shift: unchanged), F7 the ones that increased (Shift: decreased), and F9 freezes
+/// the ones left. F8, searching for a value, needs the frontend to ask for it.
+pub fn cheat_command(function_key: u8, shift: bool) -> Option<Event> {
+    match (function_key, shift) {
+        (5, _) => Some(Event::NewSearch),
+        (6, false) => Some(Event::NarrowSearch(Comparison::Changed)),
+        (6, true) => Some(Event::NarrowSearch(Comparison::Unchanged)),
+        (7, false) => Some(Event::NarrowSearch(Comparison::Increased)),
+        (7, true) => Some(Event::NarrowSearch(Comparison::Decreased)),
+        (9, _) => Some(Event::FreezeCandidates),
+        _ => None,
+    }
+}
+
+/// What `run` needs from the application around it.
+pub trait Session {
+    /// The settings of a program the user opened.
+    fn settings(&self, program: &[u8]) -> Settings;
+
+    /// Makes `settings` the defaults.
+    fn save_settings(&self, settings: &Settings) -> Result<(), String>;
+
+    /// How many pixels of the screenshots and recordings make a CHIP-8 pixel.
+    fn capture_scale(&self) -> usize;
+
+    /// Where the screenshots and recordings are written.
+    fn capture_directory(&self) -> PathBuf;
+
+    /// The cheats saved for a program.
+    fn cheats(&self, program: &[u8]) -> Result<Cheats, String>;
+
+    /// Saves the cheats of the running program, after every change.
+    fn save_cheats(&self, cheats: &Cheats) -> Result<(), String>;
+
+    /// Runs a frame of `emulator`, e.g. with the hooks of a script. Returns how many instructions were run,
+    /// and messages for the on-screen display. Fails like `Scheduler::run_frame`.
+    fn run_frame(
+        &self,
+        scheduler: &Scheduler,
+        emulator: &mut Emulator,
+    ) -> Result<(u32, Vec<String>), String> {
+        Ok((scheduler.run_frame(emulator)?, Vec::new()))
+    }
+
+    /// Another program was loaded into `emulator`: what was attached to the previous one (e.g. a script) goes.
+    /// Returns messages for the on-screen display.
+    fn loaded(&self, _emulator: &Emulator) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Runs the emulator until the frontend asks to quit, or fails. The running program was set up with `current`.
+pub fn run(
+    frontend: &mut impl Frontend,
+    emulator: &mut Emulator,
+    scheduler: &mut Scheduler,
+    screen_filter: &mut ScreenFilter,
+    mut current: Settings,
+    session: &impl Session,
+) -> Result<(), String> {
+    let mut resolution = None;
+    let mut paused = false;
+    let mut steps = 0;
+    let mut recorder: Option<Recorder> = None;
+    // The CPU or the memory changed outside of a frame
+    let mut debugger_stale = false;
+
+    let mut osd = Osd::new();
+    osd.set_quirks(current.quirks);
+
+    let mut cheats = load_cheats(session, emulator.program(), &mut osd, frontend.time());
+    let mut search: Option<Search> = None;
+    let mut cheats_stale = true;
+
+    loop {
+        let now = frontend.time();
+
+        for event in frontend.poll_input() {
+            match event {
+                Event::KeyDown(key) => emulator.key_down(key),
+                Event::KeyUp(key) => emulator.key_up(key),
+                Event::Load(program) => {
+                    let settings = session.settings(&program);
+
+                    // The running program goes on
+                    if let Err(error) = Emulator::check_size(&program, settings.load_address) {
+                        osd.message(error, now);
+                        continue;
+                    }
+
+                    emulator.load(&program, settings.load_address, settings.quirks);
+                    for message in session.loaded(emulator) {
+                        osd.message(message, now);
+                    }
+
+                    scheduler.set_instructions_per_frame(settings.instructions_per_frame);
+                    frontend.set_palette(settings.palette);
+                    frontend.set_keymap(settings.keymap);
+
+                    osd.set_quirks(settings.quirks);
+                    osd.message(settings.title.as_deref().unwrap_or("Loaded"), now);
+                    current = settings;
+                    debugger_stale = true;
+
+                    cheats = load_cheats(session, &program, &mut osd, now);
+                    search = None;
+                    cheats_stale = true;
+                }
+                Event::SaveSettings => match session.save_settings(&current) {
+                    Ok(()) => osd.message("Settings saved", now),
+                    Err(error) => osd.message(error, now),
+                },
+                Event::TogglePause => paused = !paused,
+                Event::Step if paused => steps += 1,
+                Event::Step => {}
+                Event::ToggleFastForward => {
+                    scheduler.set_fast_forward(!scheduler.fast_forward());
+
+                    if scheduler.fast_forward() {
+                        osd.message(format!("Speed x{}", scheduler::FAST_FORWARD_SPEED), now);
+                    } else {
+                        osd.message("Normal speed", now);
+                    }
+                }
+                Event::ToggleInfo => osd.toggle_info(),
+                Event::Screenshot => {
+                    let scale = session.capture_scale();
+                    let file = capture::next_file(&session.capture_directory(), "png");
+                    let image = capture::render(screen_filter, emulator.resolution(), scale);
+
+                    match capture::save_png(&file, &image, scale, &current.palette) {
+                        Ok(()) => osd.message(format!("Saved {}", file_name(&file)), now),
+                        Err(error) => osd.message(error, now),
+                    }
+                }
+                Event::ToggleRecording => match recorder.take() {
+                    Some(recorder) => match recorder.finish() {
+                        Ok(frames) => osd.message(format!("Recorded {frames} frames"), now),
+                        Err(error) => osd.message(error, now),
+                    },
+                    None => {
+                        let file = capture::next_file(&session.capture_directory(), "gif");
+
+                        match Recorder::create(&file, session.capture_scale(), &current.palette) {
+                            Ok(started) => {
+                                osd.message(format!("Recording {}", file_name(&file)), now);
+                                recorder = Some(started);
+                            }
+                            Err(error) => osd.message(error, now),
+                        }
+                    }
+                },
+                Event::Reset => {
+                    emulator.reset();
+                    osd.message("Reset", now);
+                    debugger_stale = true;
+                }
+                Event::SoftReset => {
+                    emulator.soft_reset();
+                    osd.message("Soft reset", now);
+                    debugger_stale = true;
+                }
+                Event::Poke { address, value } if paused => {
+                    emulator.poke(address, value);
+                    debugger_stale = true;
+                }
+                Event::Poke { .. } => osd.message("Pause to edit the memory", now),
+                Event::NewSearch => {
+                    search = Some(Search::new(emulator.memory()));
+                    osd.message("Searching", now);
+                    cheats_stale = true;
+                }
+                Event::NarrowSearch(comparison) => {
+                    let started = search.is_none();
+                    let active = search.get_or_insert_with(|| Search::new(emulator.memory()));
+
+                    // Nothing changed since a new search, only the values can be compared
+                    if started && !matches!(comparison, Comparison::Equal(_)) {
+                        osd.message("Searching", now);
+                    } else {
+                        active.narrow(emulator.memory(), comparison);
+                        osd.message(found(active), now);
+                    }
+                    cheats_stale = true;
+                }
+                Event::FreezeCandidates => {
+                    let candidates = search
+                        .as_ref()
+                        .map_or(Vec::new(), |search| search.candidates().to_vec());
+
+                    if candidates.is_empty() {
+                        osd.message("Nothing to freeze", now);
+                    } else if candidates.len() > cheats::MAX_FREEZE {
+                        osd.message("Search further", now);
+                    } else {
+                        for address in candidates {
+                            let value = emulator.memory()[address as usize];
+                            cheats.add(Code { address, value });
+                        }
+
+                        osd.message("Frozen", now);
+                        save_cheats(session, &cheats, &mut osd, now);
+                        search = None;
+                        cheats_stale = true;
+                    }
+                }
+                Event::ToggleCheat(index) => match cheats.toggle(index) {
+                    Some(cheat) => {
+                        let state = if cheat.enabled { "on" } else { "off" };
+                        osd.message(format!("{} {state}", cheat.code), now);
+                        save_cheats(session, &cheats, &mut osd, now);
+                        cheats_stale = true;
+                    }
+                    None => osd.message(format!("No cheat {}", index + 1), now),
+                },
+                Event::Redraw => resolution = None,
+                Event::Quit => {
+                    if let Some(recorder) = recorder {
+                        let _ = recorder.finish();
+                    }
+
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Some(error) = frontend.take_error() {
+            if let Some(recorder) = recorder {
+                let _ = recorder.finish();
+            }
+
+            return Err(error);
+        }
+
+        let mut changed = None;
+        let frames_due = scheduler.frames_due(frontend.time());
+
+        // The clock keeps going while paused, but only the frames that were asked for run
+        let frames = if paused {
+            std::mem::take(&mut steps)
+        } else {
+            frames_due
+        };
+
+        let mut instructions = 0;
+
+        for _ in 0..frames {
+            cheats.apply(emulator);
+
+            let (run, messages) = match session.run_frame(scheduler, emulator) {
+                Ok(frame) => frame,
+                Err(error) => {
+                    if let Some(recorder) = recorder {
+                        let _ = recorder.finish();
+                    }
+
+                    return Err(error);
+                }
+            };
+            instructions += run as u64;
+
+            for message in messages {
+                osd.message(message, now);
+            }
+
+            let dirty = emulator.take_dirty();
+            changed = rect::union(changed, screen_filter.update(&emulator.screen, dirty));
+
+            if let Some(active) = &mut recorder {
+                let image = capture::render(screen_filter, emulator.resolution(), active.scale());
+
+                if let Err(error) = active.add_frame(image) {
+                    osd.message(error, now);
+                    recorder = None;
+                }
+            }
+        }
+
+        // A reset while paused changed the screen outside of a frame
+        if let Some(dirty) = emulator.take_dirty() {
+            changed = rect::union(changed, screen_filter.update(&emulator.screen, Some(dirty)));
+        }
+
+        osd.set_status(&status(paused, scheduler, recorder.is_some()));
+        osd.count(frames, instructions, now);
+        osd.update(now);
+
+        let osd_changed = osd.take_changed();
+        if osd_changed {
+            frontend.set_osd(&osd.lines());
+        }
+
+        // The screen is scaled differently, the display changed (or this is the first frame): draw everything
+        if resolution != Some(emulator.resolution()) || osd_changed {
+            resolution = Some(emulator.resolution());
+            frontend.present(screen_filter, emulator.resolution(), None);
+        } else if changed.is_some() {
+            frontend.present(screen_filter, emulator.resolution(), changed);
+        }
+
+        if frames > 0 || std::mem::take(&mut debugger_stale) {
+            let written = emulator.take_written();
+
+            frontend.show_registers(&Registers::of(emulator));
+            frontend.show_memory(emulator.memory(), emulator.program_region(), &written);
+        }
+
+        if std::mem::take(&mut cheats_stale) {
+            frontend.show_cheats(cheats.cheats(), search.as_ref());
+        }
+
+        frontend.play_audio(current.audio && !paused && emulator.sound_timer > 0);
+        frontend.wait(scheduler.time_until_next_frame(frontend.time()));
+    }
+}
+
+/// The state of the emulator, shown on the screen until it changes.
+fn status(paused: bool, scheduler: &Scheduler, recording: bool) -> String {
+    let parts = [
+        (paused, "Paused"),
+        (!paused && scheduler.fast_forward(), ">> Fast"),
+        (recording, "Rec"),
+    ];
+
+    parts
+        .iter()
+        .filter(|(shown, _)| *shown)
+        .map(|(_, part)| *part)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// What the search found so far, for the on-screen display.
+fn found(search: &Search) -> String {
+    match search.candidates() {
+        [] => String::from("Nothing found"),
+        [address] => format!("Found {address:03X}"),
+        candidates => format!("{} bytes left", candidates.len()),
+    }
+}
+
+/// The cheats saved for `program`, or none if they can't be read.
+fn load_cheats(session: &impl Session, program: &[u8], osd: &mut Osd, now: Duration) -> Cheats {
+    session.cheats(program).unwrap_or_else(|error| {
+        osd.message(error, now);
+        Cheats::new(program)
+    })
+}
+
+fn save_cheats(session: &impl Session, cheats: &Cheats, osd: &mut Osd, now: Duration) {
+    if let Err(error) = session.save_cheats(cheats) {
+        osd.message(error, now);
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
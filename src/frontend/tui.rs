@@ -0,0 +1,773 @@
+use std::{
+    io::{self, Stdout, Write},
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor,
+    event::{
+        self, Event as TerminalEvent, KeyCode, KeyEventKind, KeyModifiers,
+        KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    queue,
+    style::{self, StyledContent, Stylize},
+    terminal,
+};
+
+use super::{cheat_command, keypad_key, Event, Frontend, Registers};
+use crate::{
+    cheats::{Cheat, Comparison, Search},
+    filter::ScreenFilter,
+    hexview::{HexView, Highlight, BYTES_PER_ROW},
+    keymap::{Button, Keymap},
+    osd::Overlay,
+    rect::Rect,
+    sprites::{self, SpriteWidth},
+};
+
+/// Most terminals only report key presses: a key counts as released when it hasn't been repeated for this long.
+const KEY_RELEASE_DELAY: Duration = Duration::from_millis(150);
+
+/// Pixels brighter than this are drawn as on.
+const THRESHOLD: u8 = 127;
+
+/// The row of the panel where the memory starts, below the registers.
+const MEMORY_ROW: u16 = 13;
+
+/// How far right of the registers the sprite is drawn.
+const SPRITE_COLUMN: u16 = 16;
+
+/// How many cheats the panel lists, the ones Alt+1 to Alt+9 toggle.
+const LISTED_CHEATS: usize = 9;
+
+/// How many lines of the cheats panel show the bytes left in the search.
+const CANDIDATE_LINES: usize = 2;
+
+/// How the screen is drawn with text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Glyphs {
+    /// Two pixels per character, using `▀`, `▄` and `█`.
+    HalfBlocks,
+    /// Eight pixels (2x4) per character, using the Unicode braille patterns. Fits in smaller terminals.
+    Braille,
+}
+
+impl Glyphs {
+    /// How many pixels fit in one character.
+    fn cell_size(&self) -> (usize, usize) {
+        match self {
+            Glyphs::HalfBlocks => (1, 2),
+            Glyphs::Braille => (2, 4),
+        }
+    }
+
+    /// The character showing the pixels of the screen at (`column`, `row`), or of the `overlay` over it.
+    fn glyph(
+        &self,
+        screen: &ScreenFilter,
+        overlay: &Overlay,
+        (width, height): (usize, usize),
+        column: usize,
+        row: usize,
+    ) -> char {
+        let (cell_width, cell_height) = self.cell_size();
+        let on = |dx: usize, dy: usize| {
+            let x = column * cell_width + dx;
+            let y = row * cell_height + dy;
+
+            x < width
+                && y < height
+                && overlay
+                    .pixel(x, y)
+                    .unwrap_or_else(|| screen.intensity(x, y) > THRESHOLD)
+        };
+
+        match self {
+            Glyphs::HalfBlocks => match (on(0, 0), on(0, 1)) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            },
+            Glyphs::Braille => {
+                // The dots of a braille character, in the order of their bits
+                const DOTS: [(usize, usize); 8] = [
+                    (0, 0),
+                    (0, 1),
+                    (0, 2),
+                    (1, 0),
+                    (1, 1),
+                    (1, 2),
+                    (0, 3),
+                    (1, 3),
+                ];
+
+                let bits = DOTS
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (dx, dy))| on(*dx, *dy))
+                    .fold(0, |bits, (bit, _)| bits | 1 << bit);
+
+                char::from_u32(0x2800 + bits).unwrap_or(' ')
+            }
+        }
+    }
+}
+
+/// Draws the screen in the terminal, with the registers and the memory next to it.
+/// F2 starts editing the memory: the arrows and Page Up/Down move the cursor, hex digits change the bytes
+/// (while paused), and F2 or Esc goes back to following the program counter.
+/// The sprite at I (or at the cursor, while editing) is drawn next to the registers, F4 switches between 8 and 16 pixels wide.
+/// The cheats and the search of the memory are listed under the screen: F5 to F9 search (see `cheat_command`),
+/// F8 then two hex digits searches for a value, and Alt+1 to Alt+9 toggle the cheats.
+pub struct TuiFrontend {
+    stdout: Stdout,
+    glyphs: Glyphs,
+    enhanced_keyboard: bool,
+    keymap: Keymap,
+
+    /// When each key of the keypad will be released, if it's pressed.
+    pressed: [Option<Instant>; 16],
+    events: Vec<Event>,
+
+    /// The column where the registers and the memory are drawn.
+    panel_column: u16,
+    registers: Option<Registers>,
+    hex_view: HexView,
+    sprite_width: SpriteWidth,
+
+    /// The row under the screen where the cheats are listed, and how many lines they took.
+    cheats_row: u16,
+    cheats_width: usize,
+    cheat_lines: usize,
+    cheats: Vec<Cheat>,
+    search: Option<Search>,
+    /// The hex digits typed after F8, while searching for a value.
+    value_prompt: Option<String>,
+
+    /// The lines of the on-screen display, drawn over the screen.
+    osd: Vec<String>,
+    start: Instant,
+    beeping: bool,
+
+    /// The first error writing to or reading from the terminal, returned by `take_error`.
+    error: Option<io::Error>,
+}
+
+impl TuiFrontend {
+    pub fn new(glyphs: Glyphs) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+
+        // From here, dropping the frontend on an error restores the terminal
+        let mut frontend = Self {
+            stdout: io::stdout(),
+            glyphs,
+            enhanced_keyboard: false,
+            keymap: Keymap::default(),
+
+            pressed: [None; 16],
+            events: Vec::new(),
+
+            panel_column: 0,
+            registers: None,
+            hex_view: HexView::new(),
+            sprite_width: SpriteWidth::default(),
+
+            cheats_row: 0,
+            cheats_width: 0,
+            cheat_lines: 0,
+            cheats: Vec::new(),
+            search: None,
+            value_prompt: None,
+
+            osd: Vec::new(),
+            start: Instant::now(),
+            beeping: false,
+
+            error: None,
+        };
+
+        queue!(
+            frontend.stdout,
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )?;
+
+        // Some terminals can report key releases, which is better than guessing them
+        if terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            queue!(
+                frontend.stdout,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+            frontend.enhanced_keyboard = true;
+        }
+
+        frontend.stdout.flush()?;
+
+        Ok(frontend)
+    }
+
+    /// Keeps the first error, for `take_error`.
+    fn check(&mut self, result: io::Result<()>) {
+        if let Err(error) = result {
+            self.error.get_or_insert(error);
+        }
+    }
+
+    fn press(&mut self, key: u8) {
+        if self.pressed[key as usize].is_none() {
+            self.events.push(Event::KeyDown(key));
+        }
+
+        self.pressed[key as usize] = Some(Instant::now() + KEY_RELEASE_DELAY);
+    }
+
+    fn release(&mut self, key: u8) {
+        if self.pressed[key as usize].take().is_some() {
+            self.events.push(Event::KeyUp(key));
+        }
+    }
+
+    fn read_events(&mut self) -> io::Result<()> {
+        while event::poll(Duration::ZERO)? {
+            let key = match event::read()? {
+                TerminalEvent::Key(key) => key,
+                // Everything is drawn again at the next frame, the panel too
+                TerminalEvent::Resize(..) => {
+                    queue!(self.stdout, terminal::Clear(terminal::ClearType::All))?;
+                    self.events.push(Event::Redraw);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            if self.edit_memory(key.code, ctrl, key.kind != KeyEventKind::Release)? {
+                continue;
+            }
+
+            if self.prompt_value(key.code, key.kind != KeyEventKind::Release)? {
+                continue;
+            }
+
+            if key.modifiers.contains(KeyModifiers::ALT) {
+                if let KeyCode::Char(digit @ '1'..='9') = key.code {
+                    if key.kind != KeyEventKind::Release {
+                        let index = digit as usize - '1' as usize;
+                        self.events.push(Event::ToggleCheat(index));
+                    }
+                    continue;
+                }
+            }
+
+            if key.code == KeyCode::F(4) {
+                if key.kind != KeyEventKind::Release {
+                    self.sprite_width = self.sprite_width.toggle();
+                    self.draw_sprite()?;
+                }
+                continue;
+            }
+
+            if key.code == KeyCode::Esc || (ctrl && key.code == KeyCode::Char('c')) {
+                self.events.push(Event::Quit);
+                continue;
+            }
+
+            let command = match key.code {
+                KeyCode::Char('s') if ctrl => Some(Event::SaveSettings),
+                KeyCode::Char('r') | KeyCode::Char('R') if ctrl => {
+                    if key.modifiers.contains(KeyModifiers::SHIFT) {
+                        Some(Event::Reset)
+                    } else {
+                        Some(Event::SoftReset)
+                    }
+                }
+                KeyCode::Char('p') => Some(Event::TogglePause),
+                KeyCode::Char('n') => Some(Event::Step),
+                KeyCode::Tab => Some(Event::ToggleFastForward),
+                KeyCode::F(3) => Some(Event::ToggleInfo),
+                KeyCode::F(12) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    Some(Event::ToggleRecording)
+                }
+                KeyCode::F(12) => Some(Event::Screenshot),
+                KeyCode::F(number) => {
+                    cheat_command(number, key.modifiers.contains(KeyModifiers::SHIFT))
+                }
+                _ => None,
+            };
+
+            if let Some(command) = command {
+                if key.kind != KeyEventKind::Release {
+                    self.events.push(command);
+                }
+                continue;
+            }
+
+            let keypad = match key.code {
+                KeyCode::Up => self.keymap.key(Button::Up),
+                KeyCode::Down => self.keymap.key(Button::Down),
+                KeyCode::Left => self.keymap.key(Button::Left),
+                KeyCode::Right => self.keymap.key(Button::Right),
+                KeyCode::Char(' ') => self.keymap.key(Button::A),
+                KeyCode::Enter => self.keymap.key(Button::B),
+                KeyCode::Char(character) => keypad_key(character),
+                _ => None,
+            };
+
+            if let Some(keypad) = keypad {
+                match key.kind {
+                    // The hex digits edit the memory instead
+                    _ if self.hex_view.editing() && key.kind != KeyEventKind::Release => {}
+                    KeyEventKind::Press | KeyEventKind::Repeat => self.press(keypad),
+                    KeyEventKind::Release => self.release(keypad),
+                }
+            }
+        }
+
+        // Emulate the releases the terminal doesn't report
+        if !self.enhanced_keyboard {
+            let now = Instant::now();
+
+            for key in 0..16 {
+                if self.pressed[key as usize].is_some_and(|release| release <= now) {
+                    self.release(key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles the keys of the memory editor. Returns whether `code` was one of them.
+    fn edit_memory(&mut self, code: KeyCode, ctrl: bool, pressed: bool) -> io::Result<bool> {
+        if code != KeyCode::F(2) && !self.hex_view.editing() {
+            return Ok(false);
+        }
+
+        let row = BYTES_PER_ROW as isize;
+
+        match code {
+            KeyCode::F(2) | KeyCode::Esc if pressed => self.hex_view.toggle_editing(),
+            KeyCode::Up if pressed => self.hex_view.move_cursor(-row),
+            KeyCode::Down if pressed => self.hex_view.move_cursor(row),
+            KeyCode::Left if pressed => self.hex_view.move_cursor(-1),
+            KeyCode::Right if pressed => self.hex_view.move_cursor(1),
+            KeyCode::PageUp if pressed => self.hex_view.move_page(-1),
+            KeyCode::PageDown if pressed => self.hex_view.move_page(1),
+            KeyCode::Char(character) if pressed && !ctrl && character.is_ascii_hexdigit() => {
+                let digit = character.to_digit(16).unwrap_or(0) as u8;
+
+                if let Some((address, value)) = self.hex_view.type_digit(digit) {
+                    self.events.push(Event::Poke { address, value });
+                }
+            }
+            KeyCode::F(2)
+            | KeyCode::Esc
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::PageUp
+            | KeyCode::PageDown => {}
+            _ => return Ok(false),
+        }
+
+        self.draw_memory()?;
+        Ok(true)
+    }
+
+    /// Handles F8 and the hex digits of the value to search for. Returns whether `code` was one of them.
+    fn prompt_value(&mut self, code: KeyCode, pressed: bool) -> io::Result<bool> {
+        let digits = match (&mut self.value_prompt, code) {
+            (None, KeyCode::F(8)) => {
+                if pressed {
+                    self.value_prompt = Some(String::new());
+                }
+                return self.draw_cheats().map(|_| true);
+            }
+            (None, _) => return Ok(false),
+            (Some(digits), _) => digits,
+        };
+
+        match code {
+            KeyCode::Char(character) if character.is_ascii_hexdigit() => {
+                if pressed {
+                    digits.push(character);
+                }
+            }
+            KeyCode::Backspace if pressed => {
+                digits.pop();
+            }
+            KeyCode::Esc | KeyCode::F(8) if pressed => self.value_prompt = None,
+            KeyCode::Enter if pressed && !digits.is_empty() => {
+                let value = u8::from_str_radix(digits, 16).unwrap_or(0);
+                self.events
+                    .push(Event::NarrowSearch(Comparison::Equal(value)));
+                self.value_prompt = None;
+            }
+            KeyCode::Backspace | KeyCode::Esc | KeyCode::F(8) | KeyCode::Enter => {}
+            _ => return Ok(false),
+        }
+
+        // Two digits make a byte
+        if let Some(digits) = self.value_prompt.take_if(|digits| digits.len() == 2) {
+            let value = u8::from_str_radix(&digits, 16).unwrap_or(0);
+            self.events
+                .push(Event::NarrowSearch(Comparison::Equal(value)));
+        }
+
+        self.draw_cheats()?;
+        Ok(true)
+    }
+
+    fn draw(
+        &mut self,
+        screen: &ScreenFilter,
+        resolution: (usize, usize),
+        region: Option<Rect>,
+    ) -> io::Result<()> {
+        let (cell_width, cell_height) = self.glyphs.cell_size();
+        let columns = resolution.0.div_ceil(cell_width);
+        let rows = resolution.1.div_ceil(cell_height);
+
+        // One pixel of the font per pixel of the screen
+        let overlay = Overlay::new(&self.osd, 1);
+
+        let (first_row, last_row) = match region {
+            Some(region) => (
+                region.y / cell_height,
+                region.bottom().div_ceil(cell_height).min(rows),
+            ),
+            None => {
+                queue!(self.stdout, terminal::Clear(terminal::ClearType::All))?;
+                (0, rows)
+            }
+        };
+
+        self.panel_column = columns as u16 + 2;
+        self.cheats_row = rows as u16 + 1;
+        self.cheats_width = columns;
+
+        for row in first_row..last_row {
+            let line: String = (0..columns)
+                .map(|column| self.glyphs.glyph(screen, &overlay, resolution, column, row))
+                .collect();
+
+            queue!(
+                self.stdout,
+                cursor::MoveTo(0, row as u16),
+                style::Print(line)
+            )?;
+        }
+
+        // The panel was cleared too
+        if region.is_none() {
+            if let Some(registers) = self.registers {
+                self.draw_registers(&registers)?;
+                self.draw_memory()?;
+            }
+
+            self.cheat_lines = 0;
+            self.draw_cheats()?;
+        }
+
+        self.stdout.flush()
+    }
+
+    fn draw_registers(&mut self, registers: &Registers) -> io::Result<()> {
+        let column = self.panel_column;
+
+        for (row, pair) in registers.v.chunks(2).enumerate() {
+            let index = row * 2;
+            let text = format!(
+                "V{:X} {:02X}  V{:X} {:02X}",
+                index,
+                pair[0],
+                index + 1,
+                pair[1]
+            );
+            queue!(
+                self.stdout,
+                cursor::MoveTo(column, row as u16),
+                style::Print(text)
+            )?;
+        }
+
+        let lines = [
+            format!("I  {:03X}", registers.i),
+            format!("PC {:03X}  SP {:X}", registers.pc, registers.sp),
+            format!(
+                "DT {:02X}   ST {:02X}",
+                registers.delay_timer, registers.sound_timer
+            ),
+            // Padded to erase a longer previous instruction
+            format!("{:<15}", registers.instruction.to_string()),
+        ];
+
+        for (row, line) in lines.iter().enumerate() {
+            queue!(
+                self.stdout,
+                cursor::MoveTo(column, 9 + row as u16),
+                style::Print(line)
+            )?;
+        }
+
+        self.stdout.flush()
+    }
+
+    fn draw_memory(&mut self) -> io::Result<()> {
+        let column = self.panel_column;
+        let (_, height) = terminal::size()?;
+        self.hex_view
+            .set_rows((height.saturating_sub(MEMORY_ROW + 1)) as usize);
+
+        let legend = [
+            "Memory".stylize(),
+            style(Highlight::ProgramCounter, "PC"),
+            style(Highlight::Address, "I"),
+            style(Highlight::Written, "new"),
+            style(Highlight::Font, "font"),
+            style(Highlight::Program, "rom"),
+            if self.hex_view.editing() {
+                "F2 done".stylize()
+            } else {
+                "F2 edit".stylize()
+            },
+        ];
+
+        queue!(self.stdout, cursor::MoveTo(column, MEMORY_ROW))?;
+        for label in legend {
+            queue!(
+                self.stdout,
+                style::PrintStyledContent(label),
+                style::Print(" ")
+            )?;
+        }
+
+        let rows: Vec<_> = self.hex_view.rows().collect();
+
+        for (row, start) in rows.into_iter().enumerate() {
+            queue!(
+                self.stdout,
+                cursor::MoveTo(column, MEMORY_ROW + 1 + row as u16),
+                style::Print(format!("{start:03X}"))
+            )?;
+
+            for address in start..start + BYTES_PER_ROW as u16 {
+                let text = match self.hex_view.pending_nibble(address) {
+                    Some(nibble) => format!("{nibble:X}_"),
+                    None => format!("{:02X}", self.hex_view.byte(address)),
+                };
+
+                queue!(
+                    self.stdout,
+                    style::Print(" "),
+                    style::PrintStyledContent(style(self.hex_view.highlight(address), text))
+                )?;
+            }
+        }
+
+        self.draw_sprite()
+    }
+
+    /// Draws the sprite at I, or at the cursor while editing the memory.
+    fn draw_sprite(&mut self) -> io::Result<()> {
+        let column = self.panel_column + SPRITE_COLUMN;
+        let width = self.sprite_width;
+        let height = width.default_height();
+
+        let address = self
+            .hex_view
+            .cursor()
+            .unwrap_or_else(|| self.hex_view.address());
+        let rows = sprites::rows(self.hex_view.memory(), address, height, width);
+
+        // Clear what's left of a wider sprite
+        let title = format!("{address:03X} {}x{height} F4", width.pixels());
+        queue!(
+            self.stdout,
+            cursor::MoveTo(column, 0),
+            style::Print(format!("{title:<16}"))
+        )?;
+
+        let lines = sprites::half_blocks(&rows, width);
+        for row in 0..SpriteWidth::Wide.default_height().div_ceil(2) {
+            let line = lines.get(row).map_or("", |line| line.as_str());
+
+            queue!(
+                self.stdout,
+                cursor::MoveTo(column, 1 + row as u16),
+                style::Print(" ".repeat(SpriteWidth::Wide.pixels())),
+                cursor::MoveTo(column, 1 + row as u16),
+                style::PrintStyledContent(line.white().on_dark_grey())
+            )?;
+        }
+
+        self.stdout.flush()
+    }
+
+    /// Lists the cheats under the screen, with the search and the current values of the bytes left in it.
+    fn draw_cheats(&mut self) -> io::Result<()> {
+        let width = self.cheats_width;
+        let mut lines = vec![String::from("Cheats (F5-F9, Alt+1-9)")];
+
+        for (index, cheat) in self.cheats.iter().take(LISTED_CHEATS).enumerate() {
+            let check = if cheat.enabled { 'x' } else { ' ' };
+            lines.push(format!("{} [{check}] {cheat}", index + 1));
+        }
+
+        if self.cheats.len() > LISTED_CHEATS {
+            lines.push(format!("  and {} more", self.cheats.len() - LISTED_CHEATS));
+        }
+
+        match (&self.value_prompt, &self.search) {
+            (Some(digits), _) => lines.push(format!("Search the value {digits}_")),
+            (None, Some(search)) => {
+                let step = search
+                    .steps()
+                    .last()
+                    .map_or(String::from("start"), |step| step.to_string());
+                lines.push(format!(
+                    "Search: {} left after {step}",
+                    search.candidates().len()
+                ));
+
+                // As many as fit, with their current values
+                let per_line = (width / 7).max(1);
+                let candidates: Vec<_> = search
+                    .candidates()
+                    .iter()
+                    .take(per_line * CANDIDATE_LINES)
+                    .map(|address| format!("{address:03X}={:02X}", self.hex_view.byte(*address)))
+                    .collect();
+
+                for chunk in candidates.chunks(per_line) {
+                    lines.push(chunk.join(" "));
+                }
+            }
+            (None, None) => {}
+        }
+
+        // Clear the lines left from a longer list
+        let drawn = lines.len();
+        lines.resize(drawn.max(self.cheat_lines), String::new());
+
+        for (row, line) in lines.iter().enumerate() {
+            let line: String = line.chars().take(width).collect();
+
+            queue!(
+                self.stdout,
+                cursor::MoveTo(0, self.cheats_row + row as u16),
+                style::Print(format!("{line:<width$}"))
+            )?;
+        }
+
+        self.cheat_lines = drawn;
+        self.stdout.flush()
+    }
+}
+
+/// The colors of a byte of the memory.
+fn style<D: std::fmt::Display + Stylize<Styled = StyledContent<D>>>(
+    highlight: Highlight,
+    content: D,
+) -> StyledContent<D> {
+    match highlight {
+        Highlight::Cursor => content.reverse(),
+        Highlight::ProgramCounter => content.black().on_green(),
+        Highlight::Address => content.black().on_yellow(),
+        Highlight::Written => content.red().bold(),
+        Highlight::Font => content.dark_cyan(),
+        Highlight::Program => content.white(),
+        Highlight::None => content.dark_grey(),
+    }
+}
+
+impl Frontend for TuiFrontend {
+    fn present(&mut self, screen: &ScreenFilter, resolution: (usize, usize), region: Option<Rect>) {
+        let result = self.draw(screen, resolution, region);
+        self.check(result);
+    }
+
+    fn show_registers(&mut self, registers: &Registers) {
+        self.registers = Some(*registers);
+
+        let result = self.draw_registers(registers);
+        self.check(result);
+    }
+
+    fn show_memory(&mut self, memory: &[u8], program: Range<u16>, written: &[u16]) {
+        let (pc, i) = self
+            .registers
+            .map_or((0, 0), |registers| (registers.pc, registers.i));
+
+        self.hex_view.update(memory, program, pc, i, written);
+        let result = self.draw_memory();
+        self.check(result);
+
+        // The values of the bytes left in the search changed
+        if self.search.is_some() {
+            let result = self.draw_cheats();
+            self.check(result);
+        }
+    }
+
+    fn show_cheats(&mut self, cheats: &[Cheat], search: Option<&Search>) {
+        self.cheats = cheats.to_vec();
+        self.search = search.cloned();
+
+        let result = self.draw_cheats();
+        self.check(result);
+    }
+
+    fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    fn set_osd(&mut self, lines: &[String]) {
+        self.osd = lines.to_vec();
+    }
+
+    fn poll_input(&mut self) -> Vec<Event> {
+        let result = self.read_events();
+        self.check(result);
+
+        std::mem::take(&mut self.events)
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        self.error
+            .take()
+            .map(|error| format!("Could not use the terminal: {error}"))
+    }
+
+    fn play_audio(&mut self, playing: bool) {
+        if playing && !self.beeping {
+            let _ = queue!(self.stdout, style::Print('\x07'));
+        }
+
+        self.beeping = playing;
+    }
+
+    fn time(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn wait(&mut self, timeout: Duration) {
+        // Returns early when a key is pressed
+        let _ = event::poll(timeout);
+    }
+}
+
+impl Drop for TuiFrontend {
+    fn drop(&mut self) {
+        if self.enhanced_keyboard {
+            let _ = queue!(self.stdout, PopKeyboardEnhancementFlags);
+        }
+
+        let _ = queue!(self.stdout, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = self.stdout.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,811 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{scheduler::FRAME_DURATION, Emulator};
+
+/// The registers sent by `g`, with their size in bytes. I and PC are big-endian, like the instructions in the memory.
+const REGISTERS: [(&str, usize); 21] = [
+    ("v0", 1),
+    ("v1", 1),
+    ("v2", 1),
+    ("v3", 1),
+    ("v4", 1),
+    ("v5", 1),
+    ("v6", 1),
+    ("v7", 1),
+    ("v8", 1),
+    ("v9", 1),
+    ("va", 1),
+    ("vb", 1),
+    ("vc", 1),
+    ("vd", 1),
+    ("ve", 1),
+    ("vf", 1),
+    ("i", 2),
+    ("pc", 2),
+    ("sp", 1),
+    ("dt", 1),
+    ("st", 1),
+];
+
+/// Where the register `number` starts in the reply to `g`.
+fn offset(number: usize) -> usize {
+    REGISTERS[..number].iter().map(|(_, size)| size).sum()
+}
+
+/// The largest packet the client may send.
+const PACKET_SIZE: usize = 0x4000;
+
+/// Sent by the client to interrupt the program while it runs.
+const INTERRUPT: u8 = 0x03;
+
+/// Why the program stopped, as a stop reply packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stop {
+    /// A breakpoint, or the end of a step.
+    Trap,
+    /// The program wrote to a watched byte.
+    Watch(u16),
+    /// The client interrupted the program.
+    Interrupt,
+    /// An instruction the emulator doesn't know.
+    IllegalInstruction,
+}
+
+impl Stop {
+    fn reply(self) -> String {
+        match self {
+            Stop::Trap => String::from("S05"),
+            Stop::Watch(address) => format!("T05watch:{address:x};"),
+            Stop::Interrupt => String::from("S02"),
+            Stop::IllegalInstruction => String::from("S04"),
+        }
+    }
+}
+
+/// A packet from the client.
+enum Incoming {
+    Packet(String),
+    Interrupt,
+}
+
+/// The emulator under the control of the client: the frames are split like the `Scheduler` does, but
+/// the instructions run one at a time.
+struct Target<'a> {
+    emulator: &'a mut Emulator,
+    instructions_per_frame: u32,
+    /// The instructions run in the current frame.
+    instructions: u32,
+
+    breakpoints: BTreeSet<u16>,
+    watchpoints: BTreeSet<u16>,
+}
+
+impl Target<'_> {
+    /// Runs an instruction, ending the frame when it's due.
+    fn step(&mut self) -> Option<Stop> {
+        // The program counter stays on the instruction
+        if self.emulator.run().is_err() {
+            return Some(Stop::IllegalInstruction);
+        }
+
+        self.instructions += 1;
+        if self.instructions >= self.instructions_per_frame || self.emulator.waiting_for_vblank {
+            self.instructions = 0;
+            self.emulator.vertical_blank();
+        }
+
+        if self.watchpoints.is_empty() {
+            return None;
+        }
+
+        self.emulator
+            .take_written()
+            .into_iter()
+            .find(|address| self.watchpoints.contains(address))
+            .map(Stop::Watch)
+    }
+
+    /// Runs the instructions of a frame, until a breakpoint. `resumed` runs the first instruction even
+    /// if there's a breakpoint on it, to continue from there.
+    fn run_frame(&mut self, resumed: &mut bool) -> Option<Stop> {
+        loop {
+            if !std::mem::take(resumed) && self.breakpoints.contains(&self.emulator.program_counter)
+            {
+                return Some(Stop::Trap);
+            }
+
+            if let Some(stop) = self.step() {
+                return Some(stop);
+            }
+
+            if self.instructions == 0 {
+                return None;
+            }
+        }
+    }
+
+    fn read_registers(&self) -> Vec<u8> {
+        let emulator = &self.emulator;
+        let mut bytes = emulator.registers.to_vec();
+
+        bytes.extend(emulator.address.to_be_bytes());
+        bytes.extend(emulator.program_counter.to_be_bytes());
+        bytes.extend([
+            emulator.stack_pointer,
+            emulator.delay_timer,
+            emulator.sound_timer,
+        ]);
+        bytes
+    }
+
+    /// Writes the register `number` from its bytes.
+    fn write_register(&mut self, number: usize, bytes: &[u8]) -> Result<(), ()> {
+        let emulator = &mut self.emulator;
+        let value = bytes
+            .iter()
+            .fold(0u16, |value, byte| value << 8 | *byte as u16);
+
+        match number {
+            0..=15 => emulator.registers[number] = value as u8,
+            16 => emulator.address = value & 0xFFF,
+            17 => emulator.program_counter = value & 0xFFF,
+            18 if value <= 16 => emulator.stack_pointer = value as u8,
+            19 => emulator.delay_timer = value as u8,
+            20 => emulator.sound_timer = value as u8,
+            _ => return Err(()),
+        }
+
+        Ok(())
+    }
+
+    /// Runs `monitor` commands.
+    fn monitor(&mut self, command: &str) -> String {
+        match command.trim() {
+            "screen" => {
+                let (width, height) = self.emulator.resolution();
+                let screen = self.emulator.screen();
+
+                (0..height)
+                    .map(|y| {
+                        let row: String = (0..width)
+                            .map(|x| if screen[x][y] == 1 { '#' } else { '.' })
+                            .collect();
+                        row + "\n"
+                    })
+                    .collect()
+            }
+            "reset" => {
+                self.emulator.reset();
+                self.instructions = 0;
+                String::from("The program was reloaded.\n")
+            }
+            command => format!("Unknown command '{command}', expected 'screen' or 'reset'.\n"),
+        }
+    }
+}
+
+/// A connection to the client.
+struct Connection {
+    stream: TcpStream,
+    /// What was received, but not handled yet.
+    buffer: Vec<u8>,
+    /// The client doesn't want acknowledgments (`QStartNoAckMode`).
+    no_ack: bool,
+}
+
+impl Connection {
+    /// Waits for the next packet. Returns `None` when the client disconnects.
+    fn receive(&mut self) -> io::Result<Option<Incoming>> {
+        loop {
+            if let Some(incoming) = self.parse()? {
+                return Ok(Some(incoming));
+            }
+
+            let mut bytes = [0; 1024];
+            match self.stream.read(&mut bytes)? {
+                0 => return Ok(None),
+                count => self.buffer.extend_from_slice(&bytes[..count]),
+            }
+        }
+    }
+
+    /// Whether the client interrupted the program, without waiting.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+
+        let mut bytes = [0; 1024];
+        let result = match self.stream.read(&mut bytes) {
+            Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(count) => {
+                self.buffer.extend_from_slice(&bytes[..count]);
+                Ok(())
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            Err(error) => Err(error),
+        };
+
+        self.stream.set_nonblocking(false)?;
+        result?;
+
+        match self.buffer.iter().position(|byte| *byte == INTERRUPT) {
+            Some(position) => {
+                self.buffer.remove(position);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Takes a packet out of the buffer, if there's a complete one.
+    fn parse(&mut self) -> io::Result<Option<Incoming>> {
+        // Skip the acknowledgments, and anything else between the packets
+        while let Some(&byte) = self.buffer.first() {
+            match byte {
+                b'$' => break,
+                INTERRUPT => {
+                    self.buffer.remove(0);
+                    return Ok(Some(Incoming::Interrupt));
+                }
+                _ => {
+                    self.buffer.remove(0);
+                }
+            }
+        }
+
+        let end = match self.buffer.iter().position(|byte| *byte == b'#') {
+            Some(end) if self.buffer.len() >= end + 3 => end,
+            _ => return Ok(None),
+        };
+
+        let packet: Vec<u8> = self.buffer.drain(..end + 3).collect();
+        let data = &packet[1..end];
+        let checksum = std::str::from_utf8(&packet[end + 1..])
+            .ok()
+            .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+
+        let valid = checksum == Some(sum(data));
+        if !self.no_ack {
+            self.stream.write_all(if valid { b"+" } else { b"-" })?;
+        }
+
+        // The client sends it again
+        if !valid {
+            return self.parse();
+        }
+
+        Ok(Some(Incoming::Packet(
+            String::from_utf8_lossy(data).into_owned(),
+        )))
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${data}#{:02x}", sum(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())
+    }
+}
+
+fn sum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses `address,length`.
+fn parse_range(text: &str) -> Option<(usize, usize)> {
+    let (address, length) = text.split_once(',')?;
+
+    Some((
+        usize::from_str_radix(address, 16).ok()?,
+        usize::from_str_radix(length, 16).ok()?,
+    ))
+}
+
+/// The description of the registers, for `qXfer:features:read:target.xml`.
+fn target_xml() -> String {
+    let registers: String = REGISTERS
+        .iter()
+        .map(|(name, size)| {
+            let kind = match *name {
+                "pc" => "code_ptr",
+                "i" => "data_ptr",
+                _ => "uint8",
+            };
+            format!(
+                "<reg name=\"{name}\" bitsize=\"{}\" type=\"{kind}\"/>",
+                size * 8
+            )
+        })
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+         <target version=\"1.0\"><feature name=\"org.rusty8.chip8\">{registers}</feature></target>"
+    )
+}
+
+/// Replies with the part of `document` at `offset,length` of a `qXfer` read.
+fn transfer(document: &str, range: &str) -> String {
+    let (offset, length) = match parse_range(range) {
+        Some(range) => range,
+        None => return String::from("E01"),
+    };
+
+    let bytes = document.as_bytes();
+    let start = offset.min(bytes.len());
+    let end = start.saturating_add(length).min(bytes.len());
+    let more = if end < bytes.len() { 'm' } else { 'l' };
+
+    format!("{more}{}", String::from_utf8_lossy(&bytes[start..end]))
+}
+
+/// Waits for a client of the GDB remote serial protocol on localhost at `port` (e.g. GDB with `target remote :1234`),
+/// and lets it control `emulator` until it detaches or disconnects. `instructions_per_frame` is the speed of the program
+/// when it runs.
+///
+/// The client can read and write the registers and the memory, set breakpoints (`Z0`, `Z1`) and write watchpoints (`Z2`),
+/// step and continue, and run `monitor screen` and `monitor reset`.
+pub fn serve(
+    emulator: &mut Emulator,
+    instructions_per_frame: u32,
+    port: u16,
+) -> Result<(), String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map_err(|error| format!("Could not listen on port {port}: {error}"))?;
+
+    println!("Waiting for a debugger on 127.0.0.1:{port}, e.g. gdb -ex 'target remote :{port}'");
+
+    let (stream, client) = listener
+        .accept()
+        .map_err(|error| format!("Could not accept the debugger: {error}"))?;
+    println!("Debugging for {client}");
+
+    let _ = stream.set_nodelay(true);
+    let mut connection = Connection {
+        stream,
+        buffer: Vec::new(),
+        no_ack: false,
+    };
+
+    let mut target = Target {
+        emulator,
+        instructions_per_frame: instructions_per_frame.max(1),
+        instructions: 0,
+
+        breakpoints: BTreeSet::new(),
+        watchpoints: BTreeSet::new(),
+    };
+
+    session(&mut connection, &mut target)
+        .map_err(|error| format!("Lost the connection to the debugger: {error}"))
+}
+
+fn session(connection: &mut Connection, target: &mut Target) -> io::Result<()> {
+    while let Some(incoming) = connection.receive()? {
+        let packet = match incoming {
+            Incoming::Packet(packet) => packet,
+            // Already stopped
+            Incoming::Interrupt => {
+                connection.send(&Stop::Interrupt.reply())?;
+                continue;
+            }
+        };
+
+        let reply = match packet.as_str() {
+            "?" => Stop::Trap.reply(),
+            "g" => to_hex(&target.read_registers()),
+            "k" => return Ok(()),
+            "D" => {
+                connection.send("OK")?;
+                return Ok(());
+            }
+            "QStartNoAckMode" => {
+                connection.send("OK")?;
+                connection.no_ack = true;
+                continue;
+            }
+            "qAttached" => String::from("1"),
+            "qC" => String::from("QC1"),
+            "qfThreadInfo" => String::from("m1"),
+            "qsThreadInfo" => String::from("l"),
+            "vCont?" => String::from("vCont;c;C;s;S"),
+            "s" | "vCont;s" | "vCont;s:1" => target.step().unwrap_or(Stop::Trap).reply(),
+            "c" | "vCont;c" | "vCont;c:1" => resume(connection, target)?.reply(),
+            packet => reply(packet, connection, target)?,
+        };
+
+        connection.send(&reply)?;
+    }
+
+    Ok(())
+}
+
+/// The replies to the packets with arguments, or an empty reply for the packets that aren't supported.
+fn reply(packet: &str, connection: &mut Connection, target: &mut Target) -> io::Result<String> {
+    const ERROR: &str = "E01";
+
+    // Whether `length` bytes from `address` are all in the memory
+    let size = target.emulator.memory().len();
+    let in_memory =
+        |address: usize, length: usize| address.checked_add(length).is_some_and(|end| end <= size);
+
+    if packet.starts_with("qSupported") {
+        return Ok(format!(
+            "PacketSize={PACKET_SIZE:x};qXfer:features:read+;QStartNoAckMode+;swbreak+;hwbreak+"
+        ));
+    }
+
+    if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+        return Ok(transfer(&target_xml(), range));
+    }
+
+    if let Some(command) = packet.strip_prefix("qRcmd,") {
+        let command = from_hex(command).unwrap_or_default();
+        let output = target.monitor(&String::from_utf8_lossy(&command));
+
+        // The output goes in console packets, before the reply
+        for chunk in output.as_bytes().chunks(PACKET_SIZE / 4) {
+            connection.send(&format!("O{}", to_hex(chunk)))?;
+        }
+
+        return Ok(String::from("OK"));
+    }
+
+    if packet.starts_with('H') {
+        return Ok(String::from("OK"));
+    }
+
+    let (command, arguments) = packet.split_at(1);
+
+    let reply = match command {
+        "G" => match from_hex(arguments) {
+            Some(bytes) if bytes.len() == offset(REGISTERS.len()) => {
+                let mut bytes = bytes.as_slice();
+
+                for (number, (_, size)) in REGISTERS.iter().enumerate() {
+                    let (value, rest) = bytes.split_at(*size);
+                    let _ = target.write_register(number, value);
+                    bytes = rest;
+                }
+
+                String::from("OK")
+            }
+            _ => String::from(ERROR),
+        },
+        "p" => {
+            let registers = target.read_registers();
+            let number = usize::from_str_radix(arguments, 16).ok();
+
+            match number.filter(|number| *number < REGISTERS.len()) {
+                Some(number) => to_hex(&registers[offset(number)..offset(number + 1)]),
+                None => String::from(ERROR),
+            }
+        }
+        "P" => {
+            let written = arguments.split_once('=').and_then(|(number, value)| {
+                let number = usize::from_str_radix(number, 16).ok()?;
+                let bytes = from_hex(value)?;
+
+                (REGISTERS.get(number)?.1 == bytes.len())
+                    .then(|| target.write_register(number, &bytes).ok())
+                    .flatten()
+            });
+
+            match written {
+                Some(()) => String::from("OK"),
+                None => String::from(ERROR),
+            }
+        }
+        "m" => match parse_range(arguments) {
+            Some((address, length)) if in_memory(address, length) => {
+                to_hex(&target.emulator.memory()[address..address + length])
+            }
+            _ => String::from(ERROR),
+        },
+        "M" => {
+            let written = arguments.split_once(':').and_then(|(range, data)| {
+                let (address, length) = parse_range(range)?;
+                let bytes = from_hex(data)?;
+
+                (bytes.len() == length && in_memory(address, length)).then(|| {
+                    for (offset, byte) in bytes.iter().enumerate() {
+                        target.emulator.poke((address + offset) as u16, *byte);
+                    }
+                })
+            });
+
+            match written {
+                Some(()) => String::from("OK"),
+                None => String::from(ERROR),
+            }
+        }
+        "Z" | "z" => {
+            let insert = command == "Z";
+            let mut parts = arguments.split(',');
+
+            let kind = parts.next();
+            let address = parts
+                .next()
+                .and_then(|address| u16::from_str_radix(address, 16).ok());
+            let length = parts
+                .next()
+                .and_then(|length| u16::from_str_radix(length, 16).ok())
+                .unwrap_or(1);
+
+            match (kind, address) {
+                // Software and hardware breakpoints are the same thing here
+                (Some("0" | "1"), Some(address)) => {
+                    if insert {
+                        target.breakpoints.insert(address);
+                    } else {
+                        target.breakpoints.remove(&address);
+                    }
+                    String::from("OK")
+                }
+                (Some("2"), Some(address)) => {
+                    for address in address..address.saturating_add(length.max(1)) {
+                        if insert {
+                            target.watchpoints.insert(address);
+                        } else {
+                            target.watchpoints.remove(&address);
+                        }
+                    }
+                    String::from("OK")
+                }
+                (Some(_), Some(_)) => String::new(),
+                _ => String::from(ERROR),
+            }
+        }
+        // Continuing or stepping from an address
+        "c" | "s" => match u16::from_str_radix(arguments, 16) {
+            Ok(address) => {
+                target.emulator.program_counter = address & 0xFFF;
+
+                if command == "c" {
+                    resume(connection, target)?.reply()
+                } else {
+                    target.step().unwrap_or(Stop::Trap).reply()
+                }
+            }
+            Err(_) => String::from(ERROR),
+        },
+        _ => String::new(),
+    };
+
+    Ok(reply)
+}
+
+/// Runs the program at its normal speed until it stops, or the client interrupts it.
+fn resume(connection: &mut Connection, target: &mut Target) -> io::Result<Stop> {
+    // The watched bytes the program wrote before don't count
+    target.emulator.take_written();
+
+    let mut resumed = true;
+    let mut next_frame = Instant::now();
+
+    loop {
+        if let Some(stop) = target.run_frame(&mut resumed) {
+            return Ok(stop);
+        }
+
+        if connection.interrupted()? {
+            return Ok(Stop::Interrupt);
+        }
+
+        next_frame += FRAME_DURATION;
+        thread::sleep(next_frame.saturating_duration_since(Instant::now()));
+
+        // Don't try to catch up after a slow frame
+        next_frame = next_frame.max(Instant::now() - Duration::from_millis(100));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quirks::Quirks;
+
+    /// `LD V0, #12`, `JP #200`, and an instruction that isn't supported (`SYS #000`).
+    const PROGRAM: [u8; 6] = [0x60, 0x12, 0x12, 0x00, 0x00, 0x00];
+
+    fn target(emulator: &mut Emulator) -> Target<'_> {
+        Target {
+            emulator,
+            instructions_per_frame: 10,
+            instructions: 0,
+
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+        }
+    }
+
+    /// A connection to a client on localhost, and the client's end of it.
+    fn connect() -> (Connection, TcpStream) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let connection = Connection {
+            stream,
+            buffer: Vec::new(),
+            no_ack: false,
+        };
+
+        (connection, client)
+    }
+
+    fn packet(data: &str) -> String {
+        format!("${data}#{:02x}", sum(data.as_bytes()))
+    }
+
+    /// Sends `packets` to a session on `target`, and returns its replies.
+    fn replies(target: &mut Target, packets: &[&str]) -> Vec<String> {
+        let (mut connection, mut client) = connect();
+        connection.no_ack = true;
+
+        for data in packets.iter().chain(&["k"]) {
+            client.write_all(packet(data).as_bytes()).unwrap();
+        }
+
+        session(&mut connection, target).unwrap();
+        drop(connection);
+
+        let mut received = String::new();
+        client.read_to_string(&mut received).unwrap();
+
+        received
+            .split('$')
+            .skip(1)
+            .map(|packet| packet[..packet.len() - 3].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let (mut connection, mut client) = connect();
+        let packet = |connection: &mut Connection| match connection.parse().unwrap() {
+            Some(Incoming::Packet(packet)) => Some(packet),
+            Some(Incoming::Interrupt) => Some(String::from("interrupt")),
+            None => None,
+        };
+
+        // A packet split in two
+        connection.buffer.extend(b"+$m200,2#");
+        assert_eq!(packet(&mut connection), None);
+        connection.buffer.extend(b"5d");
+        assert_eq!(packet(&mut connection).as_deref(), Some("m200,2"));
+
+        // A bad checksum is refused, and skipped
+        connection.buffer.extend(b"$g#00$g#67");
+        assert_eq!(packet(&mut connection).as_deref(), Some("g"));
+
+        connection.buffer.push(INTERRUPT);
+        assert_eq!(packet(&mut connection).as_deref(), Some("interrupt"));
+        assert_eq!(packet(&mut connection), None);
+
+        let mut acknowledgments = [0; 3];
+        client.read_exact(&mut acknowledgments).unwrap();
+        assert_eq!(&acknowledgments, b"+-+");
+    }
+
+    #[test]
+    fn transfer() {
+        assert_eq!(super::transfer("<target/>", "0,4"), "m<tar");
+        assert_eq!(super::transfer("<target/>", "4,100"), "lget/>");
+        assert_eq!(super::transfer("<target/>", "100,4"), "l");
+        assert_eq!(
+            super::transfer("<target/>", "2,ffffffffffffffff"),
+            "larget/>"
+        );
+        assert_eq!(super::transfer("<target/>", "4"), "E01");
+    }
+
+    #[test]
+    fn registers() {
+        let mut emulator = Emulator::new(&PROGRAM, Quirks::default(), 0);
+        let mut target = target(&mut emulator);
+
+        let written = format!("{}0123{}", "01".repeat(16), "0300020304");
+        let replies = replies(
+            &mut target,
+            &[
+                "s",
+                "g",
+                "p11",
+                "P0=ab",
+                "Pf=cd",
+                "P0=abcd",
+                "P12=11",
+                "P15=00",
+                "p15",
+                "p0",
+                "G00",
+                &format!("G{written}"),
+                "g",
+            ],
+        );
+
+        let registers = format!("12{}00000202000000", "00".repeat(15));
+        assert_eq!(
+            replies,
+            [
+                "S05", &registers, "0202", "OK", "OK", "E01", "E01", "E01", "E01", "ab", "E01",
+                "OK", &written,
+            ]
+        );
+    }
+
+    #[test]
+    fn memory() {
+        let mut emulator = Emulator::new(&PROGRAM, Quirks::default(), 0);
+        let mut target = target(&mut emulator);
+
+        let replies = replies(
+            &mut target,
+            &[
+                "m200,4",
+                "mffe,2",
+                "mfff,2",
+                "m1,ffffffffffffffff",
+                "M300,2:abcd",
+                "m300,2",
+                "M300,2:ab",
+                "Mfff,2:abcd",
+                "Mffffffffffffffff,1:ab",
+            ],
+        );
+
+        assert_eq!(
+            replies,
+            ["60121200", "0000", "E01", "E01", "OK", "abcd", "E01", "E01", "E01"]
+        );
+        assert_eq!(
+            target.emulator.instruction_at(0x300).to_string(),
+            "LD I, #BCD"
+        );
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut emulator = Emulator::new(&PROGRAM, Quirks::default(), 0);
+        let mut target = target(&mut emulator);
+
+        let replies = replies(
+            &mut target,
+            &[
+                "Z0,202,2", "c", "Z1,200,2", "z0,202,2", "c", "Z2,300,2", "Z3,300,2", "Z0",
+            ],
+        );
+
+        assert_eq!(replies, ["OK", "S05", "OK", "OK", "S05", "OK", "", "E01"]);
+        assert_eq!(target.emulator.program_counter, 0x200);
+        assert_eq!(target.breakpoints, BTreeSet::from([0x200]));
+        assert_eq!(target.watchpoints, BTreeSet::from([0x300, 0x301]));
+    }
+
+    #[test]
+    fn illegal_instruction() {
+        let mut emulator = Emulator::new(&PROGRAM, Quirks::default(), 0);
+        let mut target = target(&mut emulator);
+
+        assert_eq!(replies(&mut target, &["s204", "p11"]), ["S04", "0204"]);
+    }
+}
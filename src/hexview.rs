@@ -0,0 +1,229 @@
+use std::ops::Range;
+
+/// How many bytes a row of the view shows.
+pub const BYTES_PER_ROW: usize = 8;
+
+/// Where the emulator keeps the font: 16 characters of 5 bytes.
+pub const FONT: Range<u16> = 0x000..0x050;
+
+/// How many updates (frames) a byte written by the program stays highlighted.
+const RECENT_UPDATES: u8 = 60;
+
+/// Why a byte of the view stands out. When several apply, the first one wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Highlight {
+    /// The byte being edited.
+    Cursor,
+    /// The instruction about to run (two bytes).
+    ProgramCounter,
+    /// The byte I points to.
+    Address,
+    /// The program wrote to the byte recently, e.g. with FX33 or FX55.
+    Written,
+    Font,
+    /// The byte was loaded from the program file.
+    Program,
+    None,
+}
+
+/// A hex view of the memory, following the program counter, or a cursor to edit the bytes with.
+pub struct HexView {
+    memory: Vec<u8>,
+    program: Range<u16>,
+    program_counter: u16,
+    address: u16,
+
+    /// How many more updates each byte stays highlighted as written.
+    recent: Vec<u8>,
+
+    /// The address of the first row shown, and how many rows fit.
+    top: u16,
+    rows: usize,
+
+    /// The byte being edited, if editing, and the high nibble typed so far.
+    cursor: Option<u16>,
+    nibble: Option<u8>,
+}
+
+impl Default for HexView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HexView {
+    pub fn new() -> Self {
+        Self {
+            memory: vec![0; 4096],
+            program: 0..0,
+            program_counter: 0,
+            address: 0,
+
+            recent: vec![0; 4096],
+
+            top: 0,
+            rows: 16,
+
+            cursor: None,
+            nibble: None,
+        }
+    }
+
+    /// Shows a new snapshot of the memory. `written` are the addresses the program wrote to since the last update.
+    pub fn update(
+        &mut self,
+        memory: &[u8],
+        program: Range<u16>,
+        program_counter: u16,
+        address: u16,
+        written: &[u16],
+    ) {
+        self.memory = memory.to_vec();
+        self.recent.resize(memory.len(), 0);
+        self.program = program;
+        self.program_counter = program_counter;
+        self.address = address;
+
+        for frames in &mut self.recent {
+            *frames = frames.saturating_sub(1);
+        }
+
+        for address in written {
+            if let Some(frames) = self.recent.get_mut(*address as usize) {
+                *frames = RECENT_UPDATES;
+            }
+        }
+
+        self.scroll();
+    }
+
+    /// How many rows fit in the view.
+    pub fn set_rows(&mut self, rows: usize) {
+        self.rows = rows.max(1);
+        self.scroll();
+    }
+
+    /// The addresses of the first bytes of the rows shown, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = u16> {
+        let end = self.memory.len() as u16;
+
+        (self.top..end).step_by(BYTES_PER_ROW).take(self.rows)
+    }
+
+    /// The snapshot of the memory, with the edits.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// The byte being edited, if editing.
+    pub fn cursor(&self) -> Option<u16> {
+        self.cursor
+    }
+
+    /// I, in the snapshot.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    pub fn byte(&self, address: u16) -> u8 {
+        self.memory.get(address as usize).copied().unwrap_or(0)
+    }
+
+    /// The high nibble typed at `address`, while the low one is awaited.
+    pub fn pending_nibble(&self, address: u16) -> Option<u8> {
+        self.nibble.filter(|_| self.cursor == Some(address))
+    }
+
+    pub fn highlight(&self, address: u16) -> Highlight {
+        let pc = self.program_counter;
+
+        if self.cursor == Some(address) {
+            Highlight::Cursor
+        } else if address == pc || address == pc.wrapping_add(1) {
+            Highlight::ProgramCounter
+        } else if address == self.address {
+            Highlight::Address
+        } else if self
+            .recent
+            .get(address as usize)
+            .is_some_and(|frames| *frames > 0)
+        {
+            Highlight::Written
+        } else if FONT.contains(&address) {
+            Highlight::Font
+        } else if self.program.contains(&address) {
+            Highlight::Program
+        } else {
+            Highlight::None
+        }
+    }
+
+    pub fn editing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Starts editing at the program counter, or goes back to following it.
+    pub fn toggle_editing(&mut self) {
+        self.cursor = match self.cursor {
+            Some(_) => None,
+            None => Some(self.program_counter.min(self.last())),
+        };
+        self.nibble = None;
+        self.scroll();
+    }
+
+    /// Moves the cursor by `bytes`, staying in the memory.
+    pub fn move_cursor(&mut self, bytes: isize) {
+        if let Some(cursor) = self.cursor {
+            let moved = (cursor as isize + bytes).clamp(0, self.last() as isize);
+
+            self.cursor = Some(moved as u16);
+            self.nibble = None;
+            self.scroll();
+        }
+    }
+
+    /// Moves the cursor by `pages` of the rows shown.
+    pub fn move_page(&mut self, pages: isize) {
+        self.move_cursor(pages * (self.rows * BYTES_PER_ROW) as isize);
+    }
+
+    /// Types a hex digit at the cursor. Once both nibbles are typed, moves to the next byte and returns
+    /// the address and the new value, for the emulator.
+    pub fn type_digit(&mut self, digit: u8) -> Option<(u16, u8)> {
+        let cursor = self.cursor?;
+
+        match self.nibble.take() {
+            None => {
+                self.nibble = Some(digit & 0xF);
+                None
+            }
+            Some(high) => {
+                let value = high << 4 | digit & 0xF;
+                self.memory[cursor as usize] = value;
+                self.move_cursor(1);
+
+                Some((cursor, value))
+            }
+        }
+    }
+
+    fn last(&self) -> u16 {
+        self.memory.len().saturating_sub(1) as u16
+    }
+
+    /// Scrolls so that the cursor, or the program counter when not editing, is shown.
+    fn scroll(&mut self) {
+        let focus = self.cursor.unwrap_or(self.program_counter) as usize;
+        let (top, page) = (self.top as usize, self.rows * BYTES_PER_ROW);
+
+        if focus < top || focus >= top + page {
+            // Keep a row above for the context
+            let row = focus / BYTES_PER_ROW * BYTES_PER_ROW;
+            self.top = row.saturating_sub(BYTES_PER_ROW) as u16;
+        }
+
+        let last_top = self.memory.len().saturating_sub(page) / BYTES_PER_ROW * BYTES_PER_ROW;
+        self.top = self.top.min(last_top as u16);
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+/// An instruction of the CHIP-8 and the SCHIP, decoded once from its opcode by `Instruction::decode`, with its operands:
+/// `x` and `y` are registers, `byte` and `address` the values in the opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// `00CN` Scrolls the screen down by N rows.
+    ScrollDown { rows: u8 },
+    /// `00E0`
+    ClearScreen,
+    /// `00EE`
+    Return,
+    /// `00FB` Scrolls the screen right by 4 pixels.
+    ScrollRight,
+    /// `00FC` Scrolls the screen left by 4 pixels.
+    ScrollLeft,
+    /// `00FD` Exits the interpreter.
+    Exit,
+    /// `00FE` Switches to the 64x32 display mode.
+    LowRes,
+    /// `00FF` Switches to the 128x64 display mode.
+    HighRes,
+    /// `0NNN` Calls a machine code routine of the COSMAC VIP.
+    System { address: u16 },
+    /// `1NNN`
+    Jump { address: u16 },
+    /// `2NNN`
+    Call { address: u16 },
+    /// `3XNN` Skips the next instruction if VX equals NN.
+    SkipIfEqual { x: u8, byte: u8 },
+    /// `4XNN` Skips the next instruction if VX does not equal NN.
+    SkipIfNotEqual { x: u8, byte: u8 },
+    /// `5XY0` Skips the next instruction if VX equals VY.
+    SkipIfRegistersEqual { x: u8, y: u8 },
+    /// `6XNN` Sets VX to NN.
+    Load { x: u8, byte: u8 },
+    /// `7XNN` Adds NN to VX, without a carry.
+    Add { x: u8, byte: u8 },
+    /// `8XY0` Sets VX to VY.
+    Move { x: u8, y: u8 },
+    /// `8XY1`
+    Or { x: u8, y: u8 },
+    /// `8XY2`
+    And { x: u8, y: u8 },
+    /// `8XY3`
+    Xor { x: u8, y: u8 },
+    /// `8XY4` Adds VY to VX, with the carry in VF.
+    AddRegisters { x: u8, y: u8 },
+    /// `8XY5` Sets VX to VX - VY, with the borrow in VF.
+    Subtract { x: u8, y: u8 },
+    /// `8XY6`
+    ShiftRight { x: u8, y: u8 },
+    /// `8XY7` Sets VX to VY - VX, with the borrow in VF.
+    SubtractFrom { x: u8, y: u8 },
+    /// `8XYE`
+    ShiftLeft { x: u8, y: u8 },
+    /// `9XY0` Skips the next instruction if VX does not equal VY.
+    SkipIfRegistersNotEqual { x: u8, y: u8 },
+    /// `ANNN` Sets I to NNN.
+    LoadAddress { address: u16 },
+    /// `BNNN` Jumps to NNN plus V0 (or to XNN plus VX, see `Quirks::jump_with_vx`).
+    JumpWithOffset { address: u16 },
+    /// `CXNN` Sets VX to a random number and NN.
+    Random { x: u8, byte: u8 },
+    /// `DXYN` Draws N rows of the sprite at I at (VX, VY).
+    Draw { x: u8, y: u8, rows: u8 },
+    /// `EX9E` Skips the next instruction if the key in VX is pressed.
+    SkipIfPressed { x: u8 },
+    /// `EXA1` Skips the next instruction if the key in VX is not pressed.
+    SkipIfNotPressed { x: u8 },
+    /// `FX07` Sets VX to the delay timer.
+    LoadDelayTimer { x: u8 },
+    /// `FX0A` Waits for a key, and stores it in VX.
+    WaitForKey { x: u8 },
+    /// `FX15` Sets the delay timer to VX.
+    SetDelayTimer { x: u8 },
+    /// `FX18` Sets the sound timer to VX.
+    SetSoundTimer { x: u8 },
+    /// `FX1E` Adds VX to I.
+    AddToAddress { x: u8 },
+    /// `FX29` Sets I to the font sprite of the digit in VX.
+    LoadFont { x: u8 },
+    /// `FX30` Sets I to the big (SCHIP) font sprite of the digit in VX.
+    LoadBigFont { x: u8 },
+    /// `FX33` Stores the decimal digits of VX at I, I+1 and I+2.
+    StoreDecimal { x: u8 },
+    /// `FX55` Stores V0 to VX at I.
+    StoreRegisters { x: u8 },
+    /// `FX65` Loads V0 to VX from I.
+    LoadRegisters { x: u8 },
+    /// `FX75` Stores V0 to VX in the flags of the HP48.
+    StoreFlags { x: u8 },
+    /// `FX85` Loads V0 to VX from the flags of the HP48.
+    LoadFlags { x: u8 },
+    /// An opcode that isn't an instruction, e.g. the data of a sprite.
+    Unknown { opcode: u16 },
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Self {
+        let x = ((opcode >> 8) & 0x0F) as u8;
+        let y = ((opcode >> 4) & 0x0F) as u8;
+        let n = (opcode & 0x000F) as u8;
+        let byte = (opcode & 0x00FF) as u8;
+        let address = opcode & 0x0FFF;
+
+        match (opcode >> 12, x, y, n) {
+            (0x0, 0x0, 0xC, _) => Self::ScrollDown { rows: n },
+            (0x0, 0x0, 0xE, 0x0) => Self::ClearScreen,
+            (0x0, 0x0, 0xE, 0xE) => Self::Return,
+            (0x0, 0x0, 0xF, 0xB) => Self::ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) => Self::ScrollLeft,
+            (0x0, 0x0, 0xF, 0xD) => Self::Exit,
+            (0x0, 0x0, 0xF, 0xE) => Self::LowRes,
+            (0x0, 0x0, 0xF, 0xF) => Self::HighRes,
+            (0x0, _, _, _) => Self::System { address },
+            (0x1, _, _, _) => Self::Jump { address },
+            (0x2, _, _, _) => Self::Call { address },
+            (0x3, _, _, _) => Self::SkipIfEqual { x, byte },
+            (0x4, _, _, _) => Self::SkipIfNotEqual { x, byte },
+            (0x5, _, _, 0x0) => Self::SkipIfRegistersEqual { x, y },
+            (0x6, _, _, _) => Self::Load { x, byte },
+            (0x7, _, _, _) => Self::Add { x, byte },
+            (0x8, _, _, 0x0) => Self::Move { x, y },
+            (0x8, _, _, 0x1) => Self::Or { x, y },
+            (0x8, _, _, 0x2) => Self::And { x, y },
+            (0x8, _, _, 0x3) => Self::Xor { x, y },
+            (0x8, _, _, 0x4) => Self::AddRegisters { x, y },
+            (0x8, _, _, 0x5) => Self::Subtract { x, y },
+            (0x8, _, _, 0x6) => Self::ShiftRight { x, y },
+            (0x8, _, _, 0x7) => Self::SubtractFrom { x, y },
+            (0x8, _, _, 0xE) => Self::ShiftLeft { x, y },
+            (0x9, _, _, 0x0) => Self::SkipIfRegistersNotEqual { x, y },
+            (0xA, _, _, _) => Self::LoadAddress { address },
+            (0xB, _, _, _) => Self::JumpWithOffset { address },
+            (0xC, _, _, _) => Self::Random { x, byte },
+            (0xD, _, _, _) => Self::Draw { x, y, rows: n },
+            (0xE, _, 0x9, 0xE) => Self::SkipIfPressed { x },
+            (0xE, _, 0xA, 0x1) => Self::SkipIfNotPressed { x },
+            (0xF, _, 0x0, 0x7) => Self::LoadDelayTimer { x },
+            (0xF, _, 0x0, 0xA) => Self::WaitForKey { x },
+            (0xF, _, 0x1, 0x5) => Self::SetDelayTimer { x },
+            (0xF, _, 0x1, 0x8) => Self::SetSoundTimer { x },
+            (0xF, _, 0x1, 0xE) => Self::AddToAddress { x },
+            (0xF, _, 0x2, 0x9) => Self::LoadFont { x },
+            (0xF, _, 0x3, 0x0) => Self::LoadBigFont { x },
+            (0xF, _, 0x3, 0x3) => Self::StoreDecimal { x },
+            (0xF, _, 0x5, 0x5) => Self::StoreRegisters { x },
+            (0xF, _, 0x6, 0x5) => Self::LoadRegisters { x },
+            (0xF, _, 0x7, 0x5) => Self::StoreFlags { x },
+            (0xF, _, 0x8, 0x5) => Self::LoadFlags { x },
+            _ => Self::Unknown { opcode },
+        }
+    }
+
+    /// The form of the instruction in Cowgod's Chip-8 Technical Reference, without its operands, e.g. `ADD Vx, byte`.
+    /// Groups the instructions that do the same thing, like in a profile.
+    pub fn syntax(&self) -> &'static str {
+        match self {
+            Self::ScrollDown { .. } => "SCD nibble",
+            Self::ClearScreen => "CLS",
+            Self::Return => "RET",
+            Self::ScrollRight => "SCR",
+            Self::ScrollLeft => "SCL",
+            Self::Exit => "EXIT",
+            Self::LowRes => "LOW",
+            Self::HighRes => "HIGH",
+            Self::System { .. } => "SYS addr",
+            Self::Jump { .. } => "JP addr",
+            Self::Call { .. } => "CALL addr",
+            Self::SkipIfEqual { .. } => "SE Vx, byte",
+            Self::SkipIfNotEqual { .. } => "SNE Vx, byte",
+            Self::SkipIfRegistersEqual { .. } => "SE Vx, Vy",
+            Self::Load { .. } => "LD Vx, byte",
+            Self::Add { .. } => "ADD Vx, byte",
+            Self::Move { .. } => "LD Vx, Vy",
+            Self::Or { .. } => "OR Vx, Vy",
+            Self::And { .. } => "AND Vx, Vy",
+            Self::Xor { .. } => "XOR Vx, Vy",
+            Self::AddRegisters { .. } => "ADD Vx, Vy",
+            Self::Subtract { .. } => "SUB Vx, Vy",
+            Self::ShiftRight { .. } => "SHR Vx, Vy",
+            Self::SubtractFrom { .. } => "SUBN Vx, Vy",
+            Self::ShiftLeft { .. } => "SHL Vx, Vy",
+            Self::SkipIfRegistersNotEqual { .. } => "SNE Vx, Vy",
+            Self::LoadAddress { .. } => "LD I, addr",
+            Self::JumpWithOffset { .. } => "JP V0, addr",
+            Self::Random { .. } => "RND Vx, byte",
+            Self::Draw { .. } => "DRW Vx, Vy, nibble",
+            Self::SkipIfPressed { .. } => "SKP Vx",
+            Self::SkipIfNotPressed { .. } => "SKNP Vx",
+            Self::LoadDelayTimer { .. } => "LD Vx, DT",
+            Self::WaitForKey { .. } => "LD Vx, K",
+            Self::SetDelayTimer { .. } => "LD DT, Vx",
+            Self::SetSoundTimer { .. } => "LD ST, Vx",
+            Self::AddToAddress { .. } => "ADD I, Vx",
+            Self::LoadFont { .. } => "LD F, Vx",
+            Self::LoadBigFont { .. } => "LD HF, Vx",
+            Self::StoreDecimal { .. } => "LD B, Vx",
+            Self::StoreRegisters { .. } => "LD [I], Vx",
+            Self::LoadRegisters { .. } => "LD Vx, [I]",
+            Self::StoreFlags { .. } => "LD R, Vx",
+            Self::LoadFlags { .. } => "LD Vx, R",
+            Self::Unknown { .. } => "DW word",
+        }
+    }
+}
+
+/// The mnemonic of the instruction, in the syntax of Cowgod's Chip-8 Technical Reference (which the assembler reads).
+/// Opcodes that aren't instructions are shown as data, e.g. `DW #0123`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::ScrollDown { rows } => write!(f, "SCD {rows}"),
+            Self::ClearScreen => write!(f, "CLS"),
+            Self::Return => write!(f, "RET"),
+            Self::ScrollRight => write!(f, "SCR"),
+            Self::ScrollLeft => write!(f, "SCL"),
+            Self::Exit => write!(f, "EXIT"),
+            Self::LowRes => write!(f, "LOW"),
+            Self::HighRes => write!(f, "HIGH"),
+            Self::System { address } => write!(f, "SYS #{address:03X}"),
+            Self::Jump { address } => write!(f, "JP #{address:03X}"),
+            Self::Call { address } => write!(f, "CALL #{address:03X}"),
+            Self::SkipIfEqual { x, byte } => write!(f, "SE V{x:X}, #{byte:02X}"),
+            Self::SkipIfNotEqual { x, byte } => write!(f, "SNE V{x:X}, #{byte:02X}"),
+            Self::SkipIfRegistersEqual { x, y } => write!(f, "SE V{x:X}, V{y:X}"),
+            Self::Load { x, byte } => write!(f, "LD V{x:X}, #{byte:02X}"),
+            Self::Add { x, byte } => write!(f, "ADD V{x:X}, #{byte:02X}"),
+            Self::Move { x, y } => write!(f, "LD V{x:X}, V{y:X}"),
+            Self::Or { x, y } => write!(f, "OR V{x:X}, V{y:X}"),
+            Self::And { x, y } => write!(f, "AND V{x:X}, V{y:X}"),
+            Self::Xor { x, y } => write!(f, "XOR V{x:X}, V{y:X}"),
+            Self::AddRegisters { x, y } => write!(f, "ADD V{x:X}, V{y:X}"),
+            Self::Subtract { x, y } => write!(f, "SUB V{x:X}, V{y:X}"),
+            Self::ShiftRight { x, y } => write!(f, "SHR V{x:X}, V{y:X}"),
+            Self::SubtractFrom { x, y } => write!(f, "SUBN V{x:X}, V{y:X}"),
+            Self::ShiftLeft { x, y } => write!(f, "SHL V{x:X}, V{y:X}"),
+            Self::SkipIfRegistersNotEqual { x, y } => write!(f, "SNE V{x:X}, V{y:X}"),
+            Self::LoadAddress { address } => write!(f, "LD I, #{address:03X}"),
+            Self::JumpWithOffset { address } => write!(f, "JP V0, #{address:03X}"),
+            Self::Random { x, byte } => write!(f, "RND V{x:X}, #{byte:02X}"),
+            Self::Draw { x, y, rows } => write!(f, "DRW V{x:X}, V{y:X}, {rows}"),
+            Self::SkipIfPressed { x } => write!(f, "SKP V{x:X}"),
+            Self::SkipIfNotPressed { x } => write!(f, "SKNP V{x:X}"),
+            Self::LoadDelayTimer { x } => write!(f, "LD V{x:X}, DT"),
+            Self::WaitForKey { x } => write!(f, "LD V{x:X}, K"),
+            Self::SetDelayTimer { x } => write!(f, "LD DT, V{x:X}"),
+            Self::SetSoundTimer { x } => write!(f, "LD ST, V{x:X}"),
+            Self::AddToAddress { x } => write!(f, "ADD I, V{x:X}"),
+            Self::LoadFont { x } => write!(f, "LD F, V{x:X}"),
+            Self::LoadBigFont { x } => write!(f, "LD HF, V{x:X}"),
+            Self::StoreDecimal { x } => write!(f, "LD B, V{x:X}"),
+            Self::StoreRegisters { x } => write!(f, "LD [I], V{x:X}"),
+            Self::LoadRegisters { x } => write!(f, "LD V{x:X}, [I]"),
+            Self::StoreFlags { x } => write!(f, "LD R, V{x:X}"),
+            Self::LoadFlags { x } => write!(f, "LD V{x:X}, R"),
+            Self::Unknown { opcode } => write!(f, "DW #{opcode:04X}"),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// The keys of the host keyboard that a program can bind to its controls, besides the keypad layout:
+/// the arrow keys, Space (A) and Enter (B).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    B,
+}
+
+/// Which key of the hex keypad each button presses, if any. Most programs use different keys for their controls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Keymap {
+    pub up: Option<u8>,
+    pub down: Option<u8>,
+    pub left: Option<u8>,
+    pub right: Option<u8>,
+    pub a: Option<u8>,
+    pub b: Option<u8>,
+}
+
+impl Keymap {
+    /// The key of the hex keypad (0x0 to 0xF) bound to `button`.
+    pub fn key(&self, button: Button) -> Option<u8> {
+        let key = match button {
+            Button::Up => self.up,
+            Button::Down => self.down,
+            Button::Left => self.left,
+            Button::Right => self.right,
+            Button::A => self.a,
+            Button::B => self.b,
+        };
+
+        key.map(|key| key & 0x0F)
+    }
+}
+
+impl fmt::Display for Keymap {
+    /// The bound buttons, e.g. `up=5, down=8`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bound: Vec<_> = [
+            ("up", self.up),
+            ("down", self.down),
+            ("left", self.left),
+            ("right", self.right),
+            ("a", self.a),
+            ("b", self.b),
+        ]
+        .iter()
+        .filter_map(|(name, key)| key.map(|key| format!("{name}={key:X}")))
+        .collect();
+
+        if bound.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", bound.join(", "))
+        }
+    }
+}
+
+impl FromStr for Keymap {
+    type Err = String;
+
+    /// Parses buttons bound to keys of the keypad in hex, e.g. `up=5,down=8,a=6`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keymap = Keymap::default();
+
+        for binding in s
+            .split(',')
+            .map(str::trim)
+            .filter(|binding| !binding.is_empty())
+        {
+            let (button, key) = binding.split_once('=').ok_or_else(|| {
+                format!("Invalid key binding '{binding}', expected BUTTON=KEY like up=5.")
+            })?;
+
+            let key = u8::from_str_radix(key.trim(), 16)
+                .ok()
+                .filter(|key| *key <= 0x0F)
+                .ok_or_else(|| {
+                    format!("Invalid key '{key}', expected a key of the keypad from 0 to F.")
+                })?;
+
+            let slot = match button.trim() {
+                "up" => &mut keymap.up,
+                "down" => &mut keymap.down,
+                "left" => &mut keymap.left,
+                "right" => &mut keymap.right,
+                "a" => &mut keymap.a,
+                "b" => &mut keymap.b,
+                _ => {
+                    return Err(format!(
+                        "Unknown button '{button}', expected up, down, left, right, a or b."
+                    ))
+                }
+            };
+
+            *slot = Some(key);
+        }
+
+        Ok(keymap)
+    }
+}
@@ -0,0 +1,31 @@
+pub mod assembler;
+pub mod capture;
+pub mod cheats;
+pub mod config;
+pub mod coverage;
+pub mod database;
+pub mod disassembler;
+pub mod emulator;
+pub mod filter;
+pub mod frontend;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gdb;
+pub mod hexview;
+pub mod instruction;
+pub mod keymap;
+pub mod library;
+pub mod osd;
+pub mod palette;
+pub mod profiler;
+pub mod quirks;
+pub mod rect;
+pub mod render;
+pub mod scheduler;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod script;
+pub mod sprites;
+
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+pub use emulator::{Emulator, CHIP8_HEIGHT, CHIP8_WIDTH, PROGRAM_START, SCHIP_HEIGHT, SCHIP_WIDTH};
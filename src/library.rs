@@ -0,0 +1,115 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The extensions of CHIP-8, SUPER-CHIP and XO-CHIP programs.
+pub const EXTENSIONS: [&str; 3] = ["ch8", "sc8", "xo8"];
+
+/// A program that only jumps to itself, run while waiting for one to be picked.
+pub const IDLE_PROGRAM: [u8; 2] = [0x12, 0x00];
+
+/// How many programs are remembered in the recent list, unless the configuration says otherwise.
+pub const DEFAULT_RECENT_FILES: usize = 10;
+
+/// Whether the file looks like a program, from its extension.
+pub fn is_rom(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Finds the programs in a directory and its subdirectories, sorted by path. Links to directories aren't followed, so
+/// a link to a parent can't make it loop.
+pub fn scan(directory: &Path) -> Vec<PathBuf> {
+    let mut roms = Vec::new();
+    let mut directories = vec![directory.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+
+            if is_dir {
+                directories.push(path);
+            } else if is_rom(&path) {
+                roms.push(path);
+            }
+        }
+    }
+
+    roms.sort();
+    roms
+}
+
+/// Where rusty8 keeps its files: `$XDG_DATA_HOME/rusty8`, `~/.local/share/rusty8` or `%APPDATA%\rusty8`.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| {
+            env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share"))
+        })?;
+
+    Some(base.join("rusty8"))
+}
+
+/// The programs that were opened last, most recent first. Saved as one path per line.
+pub struct Recent {
+    file: Option<PathBuf>,
+    roms: Vec<PathBuf>,
+    limit: usize,
+}
+
+impl Recent {
+    /// Reads the list from the data directory, remembering up to `limit` programs. A missing or unreadable list is empty.
+    pub fn load(limit: usize) -> Self {
+        let file = data_dir().map(|directory| directory.join("recent"));
+
+        let roms = file
+            .as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .map(|contents| contents.lines().take(limit).map(PathBuf::from).collect())
+            .unwrap_or_default();
+
+        Self { file, roms, limit }
+    }
+
+    pub fn roms(&self) -> &[PathBuf] {
+        &self.roms
+    }
+
+    /// Moves `rom` to the top of the list and saves it.
+    pub fn add(&mut self, rom: &Path) -> io::Result<()> {
+        let rom = rom.canonicalize().unwrap_or_else(|_| rom.to_path_buf());
+
+        self.roms.retain(|recent| *recent != rom);
+        self.roms.insert(0, rom);
+        self.roms.truncate(self.limit);
+
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let file = match &self.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        if let Some(directory) = file.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let contents: Vec<_> = self
+            .roms
+            .iter()
+            .map(|rom| rom.display().to_string())
+            .collect();
+        fs::write(file, contents.join("\n"))
+    }
+}
@@ -1,594 +1,1025 @@
-mod input;
-
 use std::{
-    collections::VecDeque,
-    env::args,
-    fs::File,
-    io::Read,
-    sync::{Arc, Mutex},
+    cell::RefCell,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
     time::Instant,
 };
 
-use fltk::{app, prelude::*, window::Window};
-use rand::Rng;
-
-const CHIP8_WIDTH: usize = 64;
-const CHIP8_HEIGHT: usize = 32;
-
-pub struct Emulator {
-    registers: [u8; 16],
-
-    stack: [u16; 16],
-    stack_pointer: u8,
-
-    memory: [u8; 4096],
-    address: u16,
-    program_counter: u16,
-
-    screen: [[u8; CHIP8_HEIGHT]; CHIP8_WIDTH],
-    redraw: bool,
+use clap::{
+    builder::{BoolishValueParser, RangedU64ValueParser},
+    Args, Parser, Subcommand, ValueEnum,
+};
+use rusty8::{
+    assembler,
+    capture::{self, Recorder},
+    cheats::Cheats,
+    config::Config,
+    database::{self, Database, Settings},
+    disassembler,
+    filter::{Filter, ScreenFilter},
+    frontend::{
+        self,
+        fltk::FltkFrontend,
+        tui::{Glyphs, TuiFrontend},
+        Frontend, Registers, Session,
+    },
+    gdb,
+    keymap::Keymap,
+    library::{self, Recent},
+    palette::Palette,
+    profiler::Profiler,
+    quirks::{QuirkChange, Quirks},
+    scheduler::Scheduler,
+    script::Script,
+    sprites::{self, SpriteWidth},
+    Emulator,
+};
 
-    keyboard: [bool; 16],
-    waiting_for_keypress: bool,
+/// A CHIP-8 and SUPER-CHIP emulator.
+#[derive(Parser)]
+#[command(name = "rusty8", version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    delay_timer: u8,
-    sound_timer: u8,
+    #[command(flatten)]
+    run: RunArgs,
 }
 
-impl Emulator {
-    fn initialize(file: &mut File) -> Self {
-        let mut emulator = Self {
-            registers: [0u8; 16],
+#[derive(Subcommand)]
+enum Command {
+    /// Runs a program in a window or in the terminal (the default)
+    Run(RunArgs),
+
+    /// Prints the instructions of a program
+    Disasm {
+        /// The program to disassemble
+        rom: PathBuf,
+
+        /// Where the program is loaded, for the addresses
+        #[arg(long, value_parser = parse_address, default_value = "0x200")]
+        load_address: u16,
+    },
+
+    /// Draws the graphics of a program: all of it as a sprite sheet, or the sprite at an address
+    Sprites {
+        /// The program to draw
+        rom: PathBuf,
+
+        /// Draws the sprite at this address instead of the whole program
+        #[arg(long, value_parser = parse_address)]
+        address: Option<u16>,
+
+        /// How many rows the sprite at --address has [default: 15, or 16 with --wide]
+        #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        rows: Option<usize>,
+
+        /// Sprites of 16 pixels wide, two bytes per row, like the 16x16 sprites of SCHIP
+        #[arg(long)]
+        wide: bool,
+
+        /// Where the program is loaded, for the addresses
+        #[arg(long, value_parser = parse_address, default_value = "0x200")]
+        load_address: u16,
+    },
+
+    /// Assembles a program written with the mnemonics of Cowgod's Chip-8 Technical Reference
+    Asm {
+        /// The source of the program
+        source: PathBuf,
+
+        /// Where to write the program [default: the source, with the .ch8 extension]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Where the program will be loaded, for the addresses of the labels
+        #[arg(long, value_parser = parse_address, default_value = "0x200")]
+        load_address: u16,
+    },
+
+    /// Runs a program without a window, printing every instruction with the registers before it runs
+    Trace {
+        /// The program to trace
+        rom: PathBuf,
+
+        /// How many frames to run
+        #[arg(long, default_value_t = 60)]
+        frames: u32,
+
+        #[command(flatten)]
+        machine: MachineArgs,
+    },
+
+    /// Runs a program without a window as fast as possible, and reports the speed of the interpreter
+    Bench {
+        /// The program to run
+        rom: PathBuf,
+
+        /// How many instructions to run
+        #[arg(long, default_value_t = 10_000_000, value_parser = RangedU64ValueParser::<u64>::new().range(1..))]
+        instructions: u64,
+
+        /// Runs this many frames instead of a number of instructions
+        #[arg(long, conflicts_with = "instructions")]
+        frames: Option<u32>,
+
+        #[command(flatten)]
+        machine: MachineArgs,
+    },
+
+    /// Runs a program without a window, and reports where it spends its time: the most run instructions and subroutines
+    Profile {
+        /// The program to profile
+        rom: PathBuf,
+
+        /// How many frames to run
+        #[arg(long, default_value_t = 600)]
+        frames: u32,
+
+        #[command(flatten)]
+        machine: MachineArgs,
+    },
+
+    /// Runs a program without a window, and shows which bytes it executed, read as data and wrote
+    Coverage {
+        /// The program to run
+        rom: PathBuf,
+
+        /// How many frames to run
+        #[arg(long, default_value_t = 600)]
+        frames: u32,
+
+        /// A Rhai script to play the program, run before the frames (see the script command)
+        #[arg(long)]
+        script: Option<PathBuf>,
+
+        /// Prints the program as a disassembly annotated with the coverage, instead of a map of the memory
+        #[arg(long)]
+        disasm: bool,
+
+        #[command(flatten)]
+        machine: MachineArgs,
+    },
+
+    /// Runs a program without a window, and saves the screen: the last frame as a PNG, or every frame as an
+    /// animated GIF or raw RGBA pixels, depending on the extension of the output
+    Capture {
+        /// The program to run
+        rom: PathBuf,
+
+        /// Where to save the screen: a .png, a .gif, or anything else for raw RGBA frames
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// How many frames to run
+        #[arg(long, default_value_t = 60)]
+        frames: u32,
+
+        /// How many pixels of the capture make a CHIP-8 pixel [default: 10]
+        #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        scale: Option<usize>,
+
+        /// How the pixels fade: none, blend, decay[:FACTOR] or set-only[:FRAMES]
+        #[arg(long, default_value = "none", value_parser = Filter::from_str)]
+        filter: Filter,
+
+        #[command(flatten)]
+        machine: MachineArgs,
+    },
+
+    /// Runs a program without a window under the control of a debugger, with the GDB remote serial protocol
+    Gdb {
+        /// The program to debug
+        rom: PathBuf,
+
+        /// The port to listen to, on localhost
+        #[arg(long, default_value_t = 1234)]
+        port: u16,
+
+        #[command(flatten)]
+        machine: MachineArgs,
+    },
+
+    /// Runs a program without a window under the control of a Rhai script, e.g. a test scenario or a bot
+    Script {
+        /// The program to run
+        rom: PathBuf,
+
+        /// The script, run once the program is loaded
+        script: PathBuf,
+
+        /// How many frames to run after the script, with its hooks
+        #[arg(long, default_value_t = 0)]
+        frames: u32,
+
+        #[command(flatten)]
+        machine: MachineArgs,
+    },
+
+    /// Shows what the database knows about a program
+    Info {
+        /// The program to look up
+        rom: PathBuf,
+    },
+}
 
-            stack: [0u16; 16],
-            stack_pointer: 0,
+#[derive(Args)]
+struct RunArgs {
+    /// The program to run. Without one, the window starts with the launcher
+    rom: Option<PathBuf>,
 
-            memory: [0u8; 4096],
-            address: 0,
-            program_counter: 512,
+    /// Where to show the screen
+    #[arg(long, value_enum, default_value_t = FrontendKind::Fltk)]
+    frontend: FrontendKind,
 
-            screen: [[0u8; CHIP8_HEIGHT]; CHIP8_WIDTH],
-            redraw: false,
+    /// The initial size of the window, in pixels per CHIP-8 pixel [default: 10]
+    #[arg(long, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    scale: Option<usize>,
 
-            keyboard: [false; 16],
-            waiting_for_keypress: false,
+    /// Whether the sound timer beeps: on or off [default: on]
+    #[arg(long, value_name = "on|off", value_parser = BoolishValueParser::new())]
+    audio: Option<bool>,
 
-            delay_timer: 0,
-            sound_timer: 0,
-        };
+    /// How the pixels fade: none, blend, decay[:FACTOR] or set-only[:FRAMES]
+    #[arg(long, default_value = "none", value_parser = Filter::from_str)]
+    filter: Filter,
 
-        // Load the font
-        static FONT: [u8; 16 * 5] = [
-            0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-            0x20, 0x60, 0x20, 0x20, 0x70, // 1
-            0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-            0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-            0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-            0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-            0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-            0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-            0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-            0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-            0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-            0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-            0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-            0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-            0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-            0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-        ];
-        emulator.memory[..0x50].copy_from_slice(&FONT);
-
-        // Load the program
-        let data = &mut emulator.memory[0x0200..];
-        file.read(data).expect("Invalid program.");
-
-        emulator
-    }
+    /// A Rhai script run once the program is loaded, with hooks on its frames, e.g. for cheats. Opening another program stops it
+    #[arg(long)]
+    script: Option<PathBuf>,
 
-    fn next_opcode(&mut self) -> u16 {
-        let mut current: u16 = *self
-            .memory
-            .get(self.program_counter as usize)
-            .expect("Memory access out of bounds") as u16;
+    /// Prints where the program spent its time on exit, like the profile command (the last program opened)
+    #[arg(long)]
+    profile: bool,
 
-        let next: u16 = *self
-            .memory
-            .get((self.program_counter + 1) as usize)
-            .expect("Memory access out of bounds") as u16;
-
-        self.program_counter += 2;
-
-        current = current << 8;
-        current = current | next;
+    #[command(flatten)]
+    machine: MachineArgs,
+}
 
-        current
-    }
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FrontendKind {
+    /// A window
+    Fltk,
+    /// The terminal, two pixels per character
+    Tui,
+    /// The terminal, eight pixels per braille character
+    TuiBraille,
+}
 
-    fn run(&mut self) {
-        let opcode = self.next_opcode();
-        let first = opcode & 0xF000;
-        let _second = opcode & 0x0F00;
-        let third = opcode & 0x00F0;
-        let fourth = opcode & 0x000F;
-
-        match first {
-            0x0000 => match opcode & fourth {
-                0x0000 => self.clear_screen(),
-                0x000E => self.fn_return(),
-                _ => unreachable!(),
-            },
-            0x1000 => self.jump(opcode),
-            0x2000 => self.fn_call(opcode),
-            0x3000 => self.skip_if_variable_is_equal_to(opcode),
-            0x4000 => self.skip_if_variable_is_not_equal_to(opcode),
-            0x5000 => self.skip_if_variables_equal(opcode),
-            0x6000 => self.set_register_to(opcode),
-            0x7000 => self.add_to_variable(opcode),
-            0x8000 => match opcode & fourth {
-                0x0000 => self.op_assignment(opcode),
-                0x0001 => self.op_or(opcode),
-                0x0002 => self.op_and(opcode),
-                0x0003 => self.op_xor(opcode),
-                0x0004 => self.op_add(opcode),
-                0x0005 => self.op_sub(opcode),
-                0x0006 => self.op_shift_right(opcode),
-                0x0007 => self.op_sub_assign(opcode),
-                0x000E => self.op_shift_left(opcode),
-                _ => unreachable!(),
-            },
-            0x9000 => self.skip_if_variables_not_equal(opcode),
-            0xA000 => self.set_address_to(opcode),
-            0xB000 => self.jump_add(opcode),
-            0xC000 => self.bitwise_and_with_random(opcode),
-            0xD000 => self.draw_sprite(opcode),
-            0xE000 => match opcode & fourth {
-                0x0001 => self.skip_if_not_pressed(opcode),
-                0x000E => self.skip_if_pressed(opcode),
-                _ => unreachable!(),
-            },
-            0xF000 => match opcode & third {
-                0x0000 => match opcode & fourth {
-                    0x0007 => self.set_variable_to_delay_timer(opcode),
-                    0x000A => self.get_key_press(opcode),
-                    _ => unreachable!(),
-                },
-                0x0010 => match opcode & fourth {
-                    0x0005 => self.set_delay_timer_to(opcode),
-                    0x0008 => self.set_sound_timer_to(opcode),
-                    0x000E => self.add_variable_to_address(opcode),
-                    _ => unreachable!(),
-                },
-                0x0020 => match opcode & fourth {
-                    0x0009 => self.sets_address_to_font_sprite(opcode),
-                    _ => unreachable!(),
-                },
-                0x0030 => match opcode & fourth {
-                    0x0003 => self.store_variable_as_binary(opcode),
-                    _ => unreachable!(),
-                },
-                0x0050 => match opcode & fourth {
-                    0x0005 => self.dump_registers_into_memory(opcode),
-                    _ => unreachable!(),
-                },
-                0x0060 => match opcode & fourth {
-                    0x0005 => self.load_registers_from_memory(opcode),
-                    _ => unreachable!(),
-                },
-                _ => unreachable!(),
-            },
-
-            _ => unreachable!(),
-        }
-    }
+/// The initial size of the window, unless the flags or the configuration say otherwise.
+const DEFAULT_SCALE: usize = 10;
 
-    /// Clears the screen.
-    fn clear_screen(&mut self) {
-        self.screen = [[0u8; CHIP8_HEIGHT]; CHIP8_WIDTH];
-    }
+/// How to run the program, instead of its settings from the database and the configuration.
+#[derive(Args)]
+struct MachineArgs {
+    /// The platform of the CHIP-8 database, with its quirks and speed: originalChip8, modernChip8, superchip...
+    #[arg(long)]
+    platform: Option<String>,
 
-    /// Jumps to address NNN.
-    fn jump(&mut self, opcode: u16) {
-        self.program_counter = opcode & 0x0FFF;
-    }
+    /// Comma separated changes to the quirks: vip, schip, [no-]clip-sprites, [no-]count-collided-rows, [no-]display-wait, [no-]jump-with-vx
+    #[arg(long, value_delimiter = ',', value_parser = QuirkChange::from_str)]
+    quirks: Vec<QuirkChange>,
 
-    /// Calls subroutine at NNN.
-    fn fn_call(&mut self, opcode: u16) {
-        self.stack_pointer += 1;
-        self.stack[self.stack_pointer as usize] = self.program_counter;
+    /// Instructions per frame, at 60 frames per second
+    #[arg(long, value_parser = RangedU64ValueParser::<u32>::new().range(1..))]
+    speed: Option<u32>,
 
-        self.program_counter = opcode & 0x0FFF;
-    }
+    /// The colors of the unlit and lit pixels, e.g. '#000000,#FFFFFF'
+    #[arg(long, value_parser = Palette::from_str)]
+    palette: Option<Palette>,
 
-    /// Returns from a subroutine.
-    fn fn_return(&mut self) {
-        self.program_counter = self.stack[self.stack_pointer as usize];
+    /// The seed of the random number generator [default: random]
+    #[arg(long)]
+    seed: Option<u64>,
 
-        self.stack_pointer -= 1;
-    }
+    /// The keys of the keypad pressed by the arrow keys, Space (a) and Enter (b), e.g. up=5,down=8,a=6
+    #[arg(long, value_parser = Keymap::from_str)]
+    keymap: Option<Keymap>,
 
-    /// Skips the next instruction if VX equals NN.
-    fn skip_if_variable_is_equal_to(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let value = opcode & 0x00FF;
+    /// Where the program is loaded and started [default: 0x200]
+    #[arg(long, value_parser = parse_address)]
+    load_address: Option<u16>,
+}
 
-        if self.registers[vx as usize] == value as u8 {
-            self.program_counter += 2;
+impl MachineArgs {
+    /// Checks the flags that depend on the database.
+    fn check(&self, database: &Database) -> Result<(), String> {
+        match &self.platform {
+            Some(platform) if !database.platforms().any(|id| id == platform) => Err(format!(
+                "Unknown platform '{platform}', expected one of {}.",
+                database.platforms().collect::<Vec<_>>().join(", ")
+            )),
+            _ => Ok(()),
         }
     }
 
-    /// Skips the next instruction if VX does not equal NN.
-    fn skip_if_variable_is_not_equal_to(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let value = opcode & 0x00FF;
+    /// The settings of the database for `program` (or the defaults of the configuration), changed by the flags.
+    fn settings(&self, database: &Database, config: &Config, program: &[u8]) -> Settings {
+        let mut settings = database.settings_from(config.defaults(), program);
 
-        if self.registers[vx as usize] != value as u8 {
-            self.program_counter += 2;
+        if let Some(platform) = &self.platform {
+            database.apply_platform(&mut settings, platform);
         }
-    }
 
-    /// Skips the next instruction if VX equals VY.
-    fn skip_if_variables_equal(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
+        for change in &self.quirks {
+            change.apply(&mut settings.quirks);
+        }
 
-        if self.registers[vx as usize] == self.registers[vy as usize] {
-            self.program_counter += 2;
+        if let Some(speed) = self.speed {
+            settings.instructions_per_frame = speed;
         }
-    }
 
-    /// Skips the next instruction if VX does not equal VY.
-    fn skip_if_variables_not_equal(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
+        if let Some(palette) = self.palette {
+            settings.palette = palette;
+        }
 
-        if self.registers[vx as usize] != self.registers[vy as usize] {
-            self.program_counter += 2;
+        if let Some(keymap) = self.keymap {
+            settings.keymap = keymap;
         }
-    }
 
-    /// Sets VX to NN.
-    fn set_register_to(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let value = opcode & 0x00FF;
+        if let Some(load_address) = self.load_address {
+            settings.load_address = load_address;
+        }
 
-        self.registers[vx as usize] = value as u8;
+        settings
     }
 
-    /// Adds NN to VX (carry flag is not changed)
-    fn add_to_variable(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let value = opcode & 0x00FF;
+    fn emulator(&self, program: &[u8], settings: &Settings) -> Result<Emulator, String> {
+        Emulator::check_size(program, settings.load_address)?;
 
-        self.registers[vx as usize] = self.registers[vx as usize].overflowing_add(value as u8).0;
+        Ok(Emulator::with_load_address(
+            program,
+            settings.load_address,
+            settings.quirks,
+            self.seed.unwrap_or_else(rand::random),
+        ))
     }
+}
 
-    /// Sets VX to the value of VY.
-    fn op_assignment(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
+/// Parses a hex address of the memory, like `0x200` or `200`.
+fn parse_address(text: &str) -> Result<u16, String> {
+    let digits = text.trim_start_matches("0x").trim_start_matches('#');
 
-        self.registers[vx as usize] = self.registers[vy as usize];
+    match u16::from_str_radix(digits, 16) {
+        Ok(address) if address < 0x1000 => Ok(address),
+        _ => Err(format!(
+            "Invalid address '{text}', expected a hex address below 0x1000 like 0x200."
+        )),
     }
+}
 
-    /// Sets VX to VX or VY. (bitwise OR operation)
-    fn op_or(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|error| format!("Could not read {}: {error}", path.display()))
+}
 
-        self.registers[vx as usize] = self.registers[vx as usize] | self.registers[vy as usize];
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command.unwrap_or(Command::Run(cli.run)) {
+        Command::Run(args) => run(args),
+        Command::Disasm { rom, load_address } => disasm(&rom, load_address),
+        Command::Sprites {
+            rom,
+            address,
+            rows,
+            wide,
+            load_address,
+        } => sprites(&rom, address, rows, wide, load_address),
+        Command::Asm {
+            source,
+            output,
+            load_address,
+        } => asm(&source, output, load_address),
+        Command::Trace {
+            rom,
+            frames,
+            machine,
+        } => trace(&rom, frames, &machine),
+        Command::Bench {
+            rom,
+            instructions,
+            frames,
+            machine,
+        } => bench(&rom, instructions, frames, &machine),
+        Command::Profile {
+            rom,
+            frames,
+            machine,
+        } => profile(&rom, frames, &machine),
+        Command::Coverage {
+            rom,
+            frames,
+            script,
+            disasm,
+            machine,
+        } => coverage(&rom, frames, script.as_deref(), disasm, &machine),
+        Command::Capture {
+            rom,
+            output,
+            frames,
+            scale,
+            filter,
+            machine,
+        } => capture(&rom, &output, frames, scale, filter, &machine),
+        Command::Gdb { rom, port, machine } => debug(&rom, port, &machine),
+        Command::Script {
+            rom,
+            script,
+            frames,
+            machine,
+        } => run_script(&rom, &script, frames, &machine),
+        Command::Info { rom } => info(&rom),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
     }
+}
 
-    /// Sets VX to VX and VY. (bitwise AND operation)
-    fn op_and(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
-
-        self.registers[vx as usize] = self.registers[vx as usize] & self.registers[vy as usize];
-    }
+impl RunArgs {
+    fn settings(&self, database: &Database, config: &Config, program: &[u8]) -> Settings {
+        let mut settings = self.machine.settings(database, config, program);
 
-    /// Sets VX to VX xor VY.
-    fn op_xor(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
+        if let Some(audio) = self.audio {
+            settings.audio = audio;
+        }
 
-        self.registers[vx as usize] = self.registers[vx as usize] ^ self.registers[vy as usize];
+        settings
     }
+}
 
-    /// Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there is not.
-    fn op_add(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
-
-        let x = self.registers[vx as usize];
-        let y = self.registers[vy as usize];
-
-        let result = x.overflowing_add(y);
+/// The application around the emulator, for `frontend::run`.
+struct App<'a> {
+    args: &'a RunArgs,
+    database: &'a Database,
+    config: &'a Config,
+    scale: usize,
+    /// Stopped at its first error.
+    script: RefCell<Option<Script>>,
+    profiler: RefCell<Option<Profiler>>,
+}
 
-        self.registers[vx as usize] = result.0;
-        self.registers[0x0F as usize] = if result.1 { 1 } else { 0 };
+impl Session for App<'_> {
+    /// The programs opened from the launcher get the same flags.
+    fn settings(&self, program: &[u8]) -> Settings {
+        self.args.settings(self.database, self.config, program)
     }
 
-    /// VY is subtracted from VX. VF is set to 0 when there's a borrow, and 1 when there is not.
-    fn op_sub(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
-
-        let x = self.registers[vx as usize];
-        let y = self.registers[vy as usize];
-
-        let result = x.overflowing_sub(y);
-
-        self.registers[vx as usize] = result.0;
-        self.registers[0x0F as usize] = if result.1 { 1 } else { 0 };
+    /// Ctrl+S makes the settings of the running program the defaults.
+    fn save_settings(&self, settings: &Settings) -> Result<(), String> {
+        let mut config = Config::load()?;
+        config.remember(settings, self.scale);
+        config.save().map(|_| ())
     }
 
-    /// Stores the least significant bit of VX in VF and then shifts VX to the right by 1.
-    fn op_shift_right(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vf = 0x0F;
+    fn capture_scale(&self) -> usize {
+        self.scale
+    }
 
-        self.registers[vf as usize] = self.registers[vx as usize] & 1;
-        self.registers[vx as usize] = self.registers[vx as usize] >> 1;
+    fn capture_directory(&self) -> PathBuf {
+        self.config.captures()
     }
 
-    /// Sets VX to VY minus VX. VF is set to 0 when there's a borrow, and 1 when there is not.
-    fn op_sub_assign(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
+    fn cheats(&self, program: &[u8]) -> Result<Cheats, String> {
+        Cheats::load(program)
+    }
 
-        let x = self.registers[vx as usize];
-        let y = self.registers[vy as usize];
+    fn save_cheats(&self, cheats: &Cheats) -> Result<(), String> {
+        cheats.save()
+    }
 
-        let result = y.overflowing_sub(x);
+    /// What the script prints goes to the on-screen display.
+    fn run_frame(
+        &self,
+        scheduler: &Scheduler,
+        emulator: &mut Emulator,
+    ) -> Result<(u32, Vec<String>), String> {
+        let mut profiler = self.profiler.borrow_mut();
+        let mut trace = |emulator: &Emulator| {
+            if let Some(profiler) = profiler.as_mut() {
+                profiler.record(emulator);
+            }
+        };
 
-        self.registers[vx as usize] = result.0;
-        self.registers[0x0F as usize] = if result.1 { 1 } else { 0 };
-    }
+        let mut slot = self.script.borrow_mut();
 
-    /// Stores the most significant bit of VX in VF and then shifts VX to the left by 1.
-    fn op_shift_left(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vf = 0x0F;
+        let script = match slot.as_mut() {
+            Some(script) => script,
+            None => return Ok((scheduler.run_frame_traced(emulator, trace)?, Vec::new())),
+        };
 
-        self.registers[vf as usize] = self.registers[vx as usize] & (1 << 7);
-        self.registers[vx as usize] = self.registers[vx as usize] << 1;
-    }
+        match script.run_frame_traced(emulator, scheduler.instructions_per_frame(), &mut trace) {
+            Ok(instructions) => Ok((instructions, script.take_output())),
+            Err(error) => {
+                let mut messages = script.take_output();
+                messages.push(format!("Script error: {error}"));
+                *slot = None;
 
-    /// Sets I to the address NNN.
-    fn set_address_to(&mut self, opcode: u16) {
-        self.address = opcode & 0x0FFF;
+                Ok((scheduler.run_frame_traced(emulator, trace)?, messages))
+            }
+        }
     }
 
-    /// Jumps to the address NNN plus V0.
-    fn jump_add(&mut self, opcode: u16) {
-        let value = opcode & 0x0FFF;
-        let v0 = self.registers[0] as u16;
+    /// The script goes with the program it was written for, and the profile starts over.
+    fn loaded(&self, emulator: &Emulator) -> Vec<String> {
+        if let Some(profiler) = self.profiler.borrow_mut().as_mut() {
+            *profiler = Profiler::new(emulator.program_region().start);
+        }
 
-        self.address = value + v0;
+        match self.script.borrow_mut().take() {
+            Some(_) => vec![String::from("Script stopped")],
+            None => Vec::new(),
+        }
     }
+}
 
-    /// Sets VX to the result of a bitwise and operation on a random number (Typically: 0 to 255) and NN.
-    fn bitwise_and_with_random(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let value = opcode & 0x00FF;
+fn run(args: RunArgs) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    args.machine.check(&database)?;
+
+    // Without a program, the window starts with the launcher
+    let program = match &args.rom {
+        Some(rom) => read(rom)?,
+        None if args.frontend == FrontendKind::Fltk => library::IDLE_PROGRAM.to_vec(),
+        None => {
+            return Err(String::from(
+                "Expected a path to a CHIP-8 program, the terminal has no launcher.",
+            ))
+        }
+    };
 
-        self.registers[vx as usize] = rand::thread_rng().gen_range(0..=255) & value as u8;
-    }
+    let settings = args.settings(&database, &config, &program);
+    let scale = args.scale.or(config.scale).unwrap_or(DEFAULT_SCALE);
 
-    /// A key press is awaited, and then stored in VX (blocking operation, all instruction halted until next key event).
-    fn get_key_press(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
+    let mut emulator = args.machine.emulator(&program, &settings)?;
+    let mut scheduler = Scheduler::new(settings.instructions_per_frame);
+    let mut screen_filter = ScreenFilter::new(args.filter);
 
-        for (index, key) in self.keyboard.iter().enumerate() {
-            if *key {
-                self.registers[vx as usize] = index as u8;
-                self.waiting_for_keypress = false;
-                return;
+    // What the script prints at the start shows up with the first frame
+    let script = match &args.script {
+        Some(path) => {
+            let mut script = Script::load(path, true)?;
+            script.run(&mut emulator, settings.instructions_per_frame)?;
+            Some(script)
+        }
+        None => None,
+    };
+
+    let session = App {
+        args: &args,
+        database: &database,
+        config: &config,
+        scale,
+        script: RefCell::new(script),
+        profiler: RefCell::new(
+            args.profile
+                .then(|| Profiler::new(emulator.program_region().start)),
+        ),
+    };
+
+    match args.frontend {
+        FrontendKind::Fltk => {
+            let mut frontend = FltkFrontend::new(scale, Recent::load(config.recent_files()));
+            match &args.rom {
+                Some(rom) => frontend.set_rom(rom),
+                None => frontend.show_launcher(),
             }
+
+            frontend.set_palette(settings.palette);
+            frontend.set_keymap(settings.keymap);
+            frontend::run(
+                &mut frontend,
+                &mut emulator,
+                &mut scheduler,
+                &mut screen_filter,
+                settings.clone(),
+                &session,
+            )?;
+        }
+        FrontendKind::Tui | FrontendKind::TuiBraille => {
+            let glyphs = if args.frontend == FrontendKind::Tui {
+                Glyphs::HalfBlocks
+            } else {
+                Glyphs::Braille
+            };
+
+            let mut frontend = TuiFrontend::new(glyphs)
+                .map_err(|error| format!("Could not set up the terminal: {error}"))?;
+            frontend.set_keymap(settings.keymap);
+
+            // The terminal is restored (by dropping the frontend) before the error is printed
+            let result = frontend::run(
+                &mut frontend,
+                &mut emulator,
+                &mut scheduler,
+                &mut screen_filter,
+                settings.clone(),
+                &session,
+            );
+            drop(frontend);
+            result?;
         }
+    }
 
-        // This is a blocking operation, but since we can't simulate this using FLTK,
-        //  we'll repeat the same instruction until we get a key press.
-        self.program_counter -= 2;
-        self.waiting_for_keypress = true;
+    // After the terminal is restored
+    if let Some(profiler) = session.profiler.into_inner() {
+        print!("{}", profiler.report());
     }
 
-    /// Skips the next instruction if the key stored in VX is not pressed.
-    fn skip_if_not_pressed(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let key = self.registers[vx as usize];
+    Ok(())
+}
 
-        if !self.keyboard[key as usize] {
-            self.program_counter += 2;
-        }
-    }
+fn disasm(rom: &Path, load_address: u16) -> Result<(), String> {
+    let program = read(rom)?;
 
-    /// Skips the next instruction if the key stored in VX is pressed.
-    fn skip_if_pressed(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let key = self.registers[vx as usize];
+    print!("{}", disassembler::listing(&program, load_address));
+    Ok(())
+}
 
-        if self.keyboard[key as usize] {
-            self.program_counter += 2;
+fn sprites(
+    rom: &Path,
+    address: Option<u16>,
+    rows: Option<usize>,
+    wide: bool,
+    load_address: u16,
+) -> Result<(), String> {
+    let program = read(rom)?;
+    let width = if wide {
+        SpriteWidth::Wide
+    } else {
+        SpriteWidth::Narrow
+    };
+
+    let address = match address {
+        Some(address) => address,
+        None => {
+            // About 72 columns
+            let strips = 64 / width.pixels();
+            print!("{}", sprites::sheet(&program, load_address, width, strips));
+            return Ok(());
         }
+    };
+
+    // The memory as the program sees it, with the font
+    let emulator = Emulator::with_load_address(&program, load_address, Quirks::default(), 0);
+    let rows = rows.unwrap_or(width.default_height());
+    let bytes_per_row = width.bytes_per_row();
+
+    for (row, bits) in sprites::rows(emulator.memory(), address, rows, width)
+        .into_iter()
+        .enumerate()
+    {
+        let pixels: String = (0..width.pixels())
+            .rev()
+            .map(|bit| if bits >> bit & 1 == 1 { '█' } else { '.' })
+            .collect();
+
+        println!(
+            "{:03X}  {:0digits$X}  {pixels}",
+            (address as usize + row * bytes_per_row) % 0x1000,
+            bits,
+            digits = bytes_per_row * 2
+        );
     }
 
-    /// Sets VX to the value of the delay timer.
-    fn set_variable_to_delay_timer(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-
-        self.registers[vx as usize] = self.delay_timer;
-    }
+    Ok(())
+}
 
-    /// Adds VX to I. VF is not affected.
-    fn add_variable_to_address(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let value = self.registers[vx as usize];
+fn asm(source: &Path, output: Option<PathBuf>, load_address: u16) -> Result<(), String> {
+    let text = fs::read_to_string(source)
+        .map_err(|error| format!("Could not read {}: {error}", source.display()))?;
 
-        self.address += value as u16;
-    }
+    let program = assembler::assemble(&text, load_address)
+        .map_err(|error| format!("{}: {error}", source.display()))?;
 
-    /// Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels and a height of N pixels.
-    /// Each row of 8 pixels is read as bit-coded starting from memory location I; I value does not change after the execution of this instruction.
-    /// VF is set to 1 if any screen pixels are flipped from set to unset when the sprite is drawn, and to 0 if that does not happen.
-    fn draw_sprite(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let vy = (opcode & 0x00F0) >> 4;
-        let _x = self.registers[vx as usize] % CHIP8_WIDTH as u8;
-        let _y = self.registers[vy as usize] % CHIP8_HEIGHT as u8;
+    let output = output.unwrap_or_else(|| source.with_extension("ch8"));
+    fs::write(&output, &program)
+        .map_err(|error| format!("Could not write {}: {error}", output.display()))?;
 
-        let height = (opcode & 0x000F) as u8;
+    println!("Wrote {} bytes to {}", program.len(), output.display());
+    Ok(())
+}
 
-        for sprite_byte in 0..height {
-            let byte = self.memory[(self.address + sprite_byte as u16) as usize];
+/// A line of the trace: the instruction about to run, and the registers.
+fn trace_line(emulator: &Emulator) -> String {
+    let registers = Registers::of(emulator);
+    let memory = emulator.memory();
 
-            let y = (_y + sprite_byte) as usize % CHIP8_HEIGHT;
-            let mut x = _x as usize;
+    let pc = registers.pc as usize;
+    let opcode = u16::from_be_bytes([memory[pc % memory.len()], memory[(pc + 1) % memory.len()]]);
 
-            for bit in (0..8).rev() {
-                let is_on = byte & (1 << bit);
+    let v: Vec<_> = registers.v.iter().map(|v| format!("{v:02X}")).collect();
 
-                if is_on > 0 && self.screen[x][y] == 1 {
-                    self.screen[x][y] = 0;
-                    self.registers[0x0F] = 1;
-                } else if is_on > 0 && self.screen[x][y] == 0 {
-                    self.screen[x][y] = 1;
-                    self.registers[0x0F] = 0;
-                }
+    format!(
+        "{pc:03X}  {opcode:04X}  {:<16}  V={}  I={:03X}  SP={:X}  DT={:02X}  ST={:02X}",
+        registers.instruction.to_string(),
+        v.join(" "),
+        registers.i,
+        registers.sp,
+        registers.delay_timer,
+        registers.sound_timer
+    )
+}
 
-                x += 1;
-                x %= CHIP8_WIDTH;
-            }
-        }
+fn trace(rom: &Path, frames: u32, machine: &MachineArgs) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    machine.check(&database)?;
 
-        self.redraw = true;
-    }
+    let program = read(rom)?;
+    let settings = machine.settings(&database, &config, &program);
 
-    /// Sets I to the location of the sprite for the character in VX. Characters 0-F (in hexadecimal) are represented by a 4x5 font.
-    fn sets_address_to_font_sprite(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let character = self.registers[vx as usize];
+    let mut emulator = machine.emulator(&program, &settings)?;
+    let scheduler = Scheduler::new(settings.instructions_per_frame);
 
-        self.address = (character * 5) as u16;
-    }
+    let mut output = io::BufWriter::new(io::stdout().lock());
+    let mut result = Ok(());
 
-    /// Stores the binary-coded decimal representation of VX, with the hundreds digit in memory at location in I, the tens digit at location I+1, and the ones digit at location I+2.
-    fn store_variable_as_binary(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-        let value = self.registers[vx as usize];
+    for frame in 0..frames {
+        if result.is_err() {
+            break;
+        }
 
-        self.memory[(self.address + 0) as usize] = value / 100;
-        self.memory[(self.address + 1) as usize] = (value % 100) / 10;
-        self.memory[(self.address + 2) as usize] = value % 10;
+        result = writeln!(output, "; frame {frame}");
+        // The output is flushed when dropped, up to the instruction that failed
+        scheduler.run_frame_traced(&mut emulator, |emulator| {
+            if result.is_ok() {
+                result = writeln!(output, "{}", trace_line(emulator));
+            }
+        })?;
     }
 
-    /// Stores from V0 to VX (including VX) in memory, starting at address I. The offset from I is increased by 1 for each value written, but I itself is left unmodified.
-    fn dump_registers_into_memory(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
+    match result.and_then(|()| output.flush()) {
+        // The output was piped into something like `head`, which is done reading
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|error| format!("Could not write the trace: {error}")),
+    }
+}
 
-        for register in 0..(vx + 1) {
-            self.memory[(self.address + register) as usize] = self.registers[register as usize];
+/// Runs the program without waiting, until `target` instructions or `frames` frames were run.
+/// Nothing ends a frame early: the timers count down every `instructions_per_frame` instructions, like at the normal speed.
+fn bench(
+    rom: &Path,
+    target: u64,
+    frames: Option<u32>,
+    machine: &MachineArgs,
+) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    machine.check(&database)?;
+
+    let program = read(rom)?;
+    let mut settings = machine.settings(&database, &config, &program);
+
+    // The sprites waiting for the display would measure the frames instead of the interpreter
+    settings.quirks.display_wait = false;
+
+    let mut emulator = machine.emulator(&program, &settings)?;
+    let per_frame = settings.instructions_per_frame.max(1) as u64;
+    let target = frames.map_or(target, |frames| frames as u64 * per_frame);
+
+    let start = Instant::now();
+    let mut instructions = 0u64;
+
+    while instructions < target {
+        emulator.run()?;
+        instructions += 1;
+
+        if instructions.is_multiple_of(per_frame) {
+            emulator.vertical_blank();
         }
     }
 
-    /// Fills from V0 to VX (including VX) with values from memory, starting at address I.
-    /// The offset from I is increased by 1 for each value read, but I itself is left unmodified.
-    fn load_registers_from_memory(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
+    let seconds = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    let per_second = instructions as f64 / seconds;
+    let run = instructions as f64 / per_frame as f64;
+
+    println!(
+        "{instructions} instructions, {} frames in {seconds:.3}s",
+        instructions.div_ceil(per_frame)
+    );
+    println!(
+        "{per_second:.0} instructions per second ({:.1} MIPS), {:.1}x real time",
+        per_second / 1_000_000.0,
+        run / 60.0 / seconds
+    );
+
+    Ok(())
+}
 
-        for register in 0..(vx + 1) {
-            let result = self.memory[(self.address + register) as usize];
+fn profile(rom: &Path, frames: u32, machine: &MachineArgs) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    machine.check(&database)?;
 
-            self.registers[register as usize] = result;
-        }
-    }
+    let program = read(rom)?;
+    let settings = machine.settings(&database, &config, &program);
 
-    /// Sets the delay timer to VX.
-    fn set_delay_timer_to(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
+    let mut emulator = machine.emulator(&program, &settings)?;
+    let scheduler = Scheduler::new(settings.instructions_per_frame);
+    let mut profiler = Profiler::new(emulator.program_region().start);
 
-        self.delay_timer = self.registers[vx as usize];
+    for _ in 0..frames {
+        scheduler.run_frame_traced(&mut emulator, |emulator| profiler.record(emulator))?;
     }
 
-    /// Sets the sound timer to VX.
-    fn set_sound_timer_to(&mut self, opcode: u16) {
-        let vx = (opcode & 0x0F00) >> 8;
-
-        self.sound_timer = self.registers[vx as usize];
-    }
+    print!("{}", profiler.report());
+    Ok(())
 }
 
-fn main() {
-    let mut file = File::open(
-        args()
-            .nth(1)
-            .expect("Expected a path to a CHIP-8 program as the first argument."),
-    )
-    .expect("File not found!");
-
-    let mut emulator = Emulator::initialize(&mut file);
-
-    //
-    // GUI
-    //
-    const RATIO: usize = 10;
-    const WIDTH: i32 = (CHIP8_WIDTH * RATIO) as i32;
-    const HEIGHT: i32 = (CHIP8_HEIGHT * RATIO) as i32;
+fn coverage(
+    rom: &Path,
+    frames: u32,
+    script: Option<&Path>,
+    disasm: bool,
+    machine: &MachineArgs,
+) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    machine.check(&database)?;
+
+    let program = read(rom)?;
+    let settings = machine.settings(&database, &config, &program);
+
+    let mut emulator = machine.emulator(&program, &settings)?;
+    let scheduler = Scheduler::new(settings.instructions_per_frame);
+
+    match script {
+        Some(path) => {
+            let mut script = Script::load(path, false)?;
+            script.run(&mut emulator, settings.instructions_per_frame)?;
+
+            for _ in 0..frames {
+                script.run_frame(&mut emulator, settings.instructions_per_frame)?;
+            }
+        }
+        None => {
+            for _ in 0..frames {
+                scheduler.run_frame(&mut emulator)?;
+            }
+        }
+    }
 
-    let mut window = Window::default()
-        .with_size(WIDTH, HEIGHT)
-        .with_label("Rusty8");
+    let coverage = emulator.coverage();
+    let region = emulator.program_region();
+
+    if disasm {
+        print!(
+            "{}",
+            disassembler::annotated(&program, settings.load_address, coverage)
+        );
+    } else {
+        print!(
+            "{}\n{}",
+            coverage.summary(region.clone()),
+            coverage.map(region)
+        );
+    }
 
-    let mut frame = fltk::frame::Frame::default().size_of(&window);
+    Ok(())
+}
 
-    window.end();
-    window.make_resizable(false);
-    window.show();
+fn capture(
+    rom: &Path,
+    output: &Path,
+    frames: u32,
+    scale: Option<usize>,
+    filter: Filter,
+    machine: &MachineArgs,
+) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    machine.check(&database)?;
+
+    let program = read(rom)?;
+    let settings = machine.settings(&database, &config, &program);
+    let scale = scale.or(config.scale).unwrap_or(DEFAULT_SCALE);
+
+    let mut emulator = machine.emulator(&program, &settings)?;
+    let scheduler = Scheduler::new(settings.instructions_per_frame);
+    let mut screen_filter = ScreenFilter::new(filter);
+
+    let png = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+
+    // A screenshot only needs the last frame
+    let mut recorder = if png {
+        None
+    } else {
+        Some(Recorder::create(output, scale, &settings.palette)?)
+    };
+
+    for _ in 0..frames {
+        scheduler.run_frame(&mut emulator)?;
+
+        let dirty = emulator.take_dirty();
+        screen_filter.update(emulator.screen(), dirty);
+
+        if let Some(recorder) = &mut recorder {
+            recorder.add_frame(capture::render(
+                &screen_filter,
+                emulator.resolution(),
+                scale,
+            ))?;
+        }
+    }
 
-    let mut frame_buffer = vec![0; (WIDTH * HEIGHT * 4) as usize];
-    let mut delay_timer = Instant::now();
-    let mut sound_timer = Instant::now();
+    let (width, height) = capture::size(scale);
 
-    let keyboard: Arc<Mutex<VecDeque<char>>> = Arc::new(Mutex::new(VecDeque::with_capacity(10)));
-    let keyboard_events: Arc<Mutex<VecDeque<char>>> = keyboard.clone();
+    match recorder {
+        None => {
+            let image = capture::render(&screen_filter, emulator.resolution(), scale);
+            capture::save_png(output, &image, scale, &settings.palette)?;
+            println!("Saved {width}x{height} screenshot to {}", output.display());
+        }
+        Some(recorder) if recorder.is_gif() => {
+            let frames = recorder.finish()?;
+            println!(
+                "Recorded {frames} frames of {width}x{height} to {}",
+                output.display()
+            );
+        }
+        Some(recorder) => {
+            let frames = recorder.finish()?;
+            println!(
+                "Recorded {frames} frames of {width}x{height} RGBA to {}, play them with",
+                output.display()
+            );
+            println!(
+                "  ffplay -f rawvideo -pixel_format rgba -video_size {width}x{height} -framerate 60 {}",
+                output.display()
+            );
+        }
+    }
 
-    input::handle_events(&mut window, keyboard_events);
+    Ok(())
+}
 
-    app::add_idle3(move |_| {
-        input::handle_keyboard(&mut emulator, &keyboard);
+fn debug(rom: &Path, port: u16, machine: &MachineArgs) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    machine.check(&database)?;
 
-        emulator.run();
+    let program = read(rom)?;
+    let settings = machine.settings(&database, &config, &program);
 
-        if emulator.delay_timer > 0 && delay_timer.elapsed().as_millis() >= 16 {
-            emulator.delay_timer -= 1;
-            delay_timer = Instant::now();
-        }
+    let mut emulator = machine.emulator(&program, &settings)?;
+    gdb::serve(&mut emulator, settings.instructions_per_frame, port)
+}
 
-        if emulator.sound_timer > 0 && sound_timer.elapsed().as_millis() >= 16 {
-            emulator.sound_timer -= 1;
-            sound_timer = Instant::now();
-        }
+fn run_script(rom: &Path, path: &Path, frames: u32, machine: &MachineArgs) -> Result<(), String> {
+    let config = Config::load()?;
+    let database = Database::load()?;
+    machine.check(&database)?;
 
-        if emulator.redraw {
-            for (i, pixel) in frame_buffer.chunks_exact_mut(4).enumerate() {
-                let x = i % WIDTH as usize;
-                let y = i / WIDTH as usize;
+    let program = read(rom)?;
+    let settings = machine.settings(&database, &config, &program);
 
-                let color = emulator.screen[x / RATIO][y / RATIO];
+    let mut emulator = machine.emulator(&program, &settings)?;
+    let mut script = Script::load(path, false)?;
 
-                let rgba = if color == 0 {
-                    [0, 0, 0, 255]
-                } else {
-                    [255, 255, 255, 255]
-                };
+    script.run(&mut emulator, settings.instructions_per_frame)?;
 
-                pixel.copy_from_slice(&rgba)
-            }
+    for _ in 0..frames {
+        script.run_frame(&mut emulator, settings.instructions_per_frame)?;
+    }
 
-            fltk::draw::draw_rgba(&mut frame, &frame_buffer).unwrap();
-            window.redraw();
-        }
-    });
+    Ok(())
+}
 
-    app::App::default().run().unwrap();
+fn info(rom: &Path) -> Result<(), String> {
+    let database = Database::load()?;
+    let program = read(rom)?;
+    let settings = database.settings(&program);
+
+    println!("File:          {}", rom.display());
+    println!("Size:          {} bytes", program.len());
+    println!("SHA-1:         {}", database::hash(&program));
+    println!(
+        "Title:         {}",
+        settings.title.as_deref().unwrap_or("(not in the database)")
+    );
+    println!(
+        "Platform:      {}",
+        settings.platform.as_deref().unwrap_or("(unknown)")
+    );
+    println!("Quirks:        {}", settings.quirks);
+    println!(
+        "Speed:         {} instructions per frame",
+        settings.instructions_per_frame
+    );
+    println!("Palette:       {}", settings.palette);
+    println!("Keymap:        {}", settings.keymap);
+    println!("Load address:  {:#05X}", settings.load_address);
+
+    Ok(())
 }
@@ -0,0 +1,283 @@
+use std::time::Duration;
+
+use crate::quirks::Quirks;
+
+/// How long a message stays on the screen.
+const MESSAGE_DURATION: Duration = Duration::from_secs(2);
+
+/// At most this many messages are shown at once, the oldest ones go first.
+const MAX_MESSAGES: usize = 3;
+
+/// How often the counters are updated.
+const COUNTER_PERIOD: Duration = Duration::from_secs(1);
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+/// The size of a character, with the space after it.
+const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+
+/// The height of a line: the glyphs, with a pixel of the box above and below.
+const LINE_HEIGHT: usize = GLYPH_HEIGHT + 2;
+
+/// The rows of a character of the font, top to bottom. Bit 2 is the left pixel, bit 0 the right one.
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b011, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '\\' => [0b100, 0b100, 0b010, 0b001, 0b001],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b011, 0b010, 0b010, 0b010, 0b011],
+        ']' => [0b110, 0b010, 0b010, 0b010, 0b110],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        _ => [0b111, 0b101, 0b101, 0b101, 0b111],
+    }
+}
+
+/// Lines of text drawn in the top left corner of the display, white on a dark box, with the built-in font.
+pub struct Overlay<'a> {
+    lines: &'a [String],
+    /// How many pixels of the display make a pixel of the font.
+    scale: usize,
+}
+
+impl<'a> Overlay<'a> {
+    pub fn new(lines: &'a [String], scale: usize) -> Self {
+        Self {
+            lines,
+            scale: scale.max(1),
+        }
+    }
+
+    /// The width and height of the part of the display the overlay may cover.
+    pub fn size(&self) -> (usize, usize) {
+        let columns = self
+            .lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        (
+            (columns * CELL_WIDTH + 1) * self.scale,
+            self.lines.len() * LINE_HEIGHT * self.scale,
+        )
+    }
+
+    /// What covers the pixel at (x, y): `Some(true)` for the text, `Some(false)` for the box around it,
+    /// or `None` if the screen shows through.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<bool> {
+        let (x, y) = (x / self.scale, y / self.scale);
+        let line = self.lines.get(y / LINE_HEIGHT)?;
+
+        // The box starts with a column of padding
+        let column = x.checked_sub(1).map(|x| x / CELL_WIDTH);
+        let character = match column {
+            Some(column) => Some(line.chars().nth(column)?),
+            None if line.is_empty() => return None,
+            None => None,
+        };
+
+        let (dx, dy) = ((x + CELL_WIDTH - 1) % CELL_WIDTH, y % LINE_HEIGHT);
+
+        let text = match character {
+            Some(character) if dx < GLYPH_WIDTH && (1..=GLYPH_HEIGHT).contains(&dy) => {
+                glyph(character)[dy - 1] >> (GLYPH_WIDTH - 1 - dx) & 1 == 1
+            }
+            _ => false,
+        };
+
+        Some(text)
+    }
+}
+
+/// What the on-screen display shows: transient messages, the state of the emulator (paused...),
+/// and optionally the speed and the quirks.
+pub struct Osd {
+    /// The messages, and when they disappear.
+    messages: Vec<(String, Duration)>,
+    status: String,
+
+    show_info: bool,
+    quirks: Quirks,
+
+    /// The frames and instructions run since `counting_since`, and the last rates.
+    frames: u32,
+    instructions: u64,
+    counting_since: Duration,
+    rates: Option<(f64, f64)>,
+
+    changed: bool,
+}
+
+impl Default for Osd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Osd {
+    pub fn new() -> Self {
+        Self {
+            messages: Vec::new(),
+            status: String::new(),
+
+            show_info: false,
+            quirks: Quirks::default(),
+
+            frames: 0,
+            instructions: 0,
+            counting_since: Duration::ZERO,
+            rates: None,
+
+            changed: false,
+        }
+    }
+
+    /// Shows `message` for a couple of seconds, from `now`.
+    pub fn message(&mut self, message: impl Into<String>, now: Duration) {
+        self.messages.push((message.into(), now + MESSAGE_DURATION));
+
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+
+        self.changed = true;
+    }
+
+    /// Shows `status` until it's changed, e.g. `Paused`. Empty to show nothing.
+    pub fn set_status(&mut self, status: &str) {
+        if self.status != status {
+            self.status = status.to_string();
+            self.changed = true;
+        }
+    }
+
+    /// Shows or hides the counters of frames and instructions per second, and the quirks.
+    pub fn toggle_info(&mut self) {
+        self.show_info = !self.show_info;
+        self.changed = true;
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+        self.changed |= self.show_info;
+    }
+
+    /// Counts the frames and instructions run at `now`, for the counters.
+    pub fn count(&mut self, frames: u32, instructions: u64, now: Duration) {
+        self.frames += frames;
+        self.instructions += instructions;
+
+        let elapsed = now.saturating_sub(self.counting_since);
+        if elapsed >= COUNTER_PERIOD {
+            let seconds = elapsed.as_secs_f64();
+            self.rates = Some((
+                self.frames as f64 / seconds,
+                self.instructions as f64 / seconds,
+            ));
+
+            self.frames = 0;
+            self.instructions = 0;
+            self.counting_since = now;
+            self.changed |= self.show_info;
+        }
+    }
+
+    /// Removes the messages that expired at `now`.
+    pub fn update(&mut self, now: Duration) {
+        let count = self.messages.len();
+        self.messages.retain(|(_, until)| *until > now);
+
+        self.changed |= self.messages.len() != count;
+    }
+
+    /// Whether the lines changed since the last call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// The lines to draw, top to bottom.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if !self.status.is_empty() {
+            lines.push(self.status.clone());
+        }
+
+        if self.show_info {
+            lines.push(match self.rates {
+                Some((fps, ips)) => format!("{fps:.0} FPS {ips:.0} IPS"),
+                None => String::from("- FPS - IPS"),
+            });
+            lines.push(format!("Quirks: {}", profile(self.quirks)));
+        }
+
+        lines.extend(self.messages.iter().map(|(message, _)| message.clone()));
+        lines
+    }
+}
+
+/// A short name for the quirks: the preset they match, or the quirks that are enabled.
+fn profile(quirks: Quirks) -> String {
+    if quirks == Quirks::vip() {
+        String::from("VIP")
+    } else if quirks == Quirks::schip() {
+        String::from("SCHIP")
+    } else {
+        quirks.to_string()
+    }
+}
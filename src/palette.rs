@@ -0,0 +1,103 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// An RGB color.
+pub type Color = [u8; 3];
+
+/// The colors of the unlit and lit pixels. Saved as `BACKGROUND,FOREGROUND`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Palette {
+    pub background: Color,
+    pub foreground: Color,
+}
+
+impl Palette {
+    pub const fn new(background: Color, foreground: Color) -> Self {
+        Self {
+            background,
+            foreground,
+        }
+    }
+
+    /// The RGBA color of a pixel of the given intensity, between the background (0) and the foreground (255).
+    pub fn color(&self, intensity: u8) -> [u8; 4] {
+        let mix = |background: u8, foreground: u8| {
+            let (background, foreground, intensity) =
+                (background as u32, foreground as u32, intensity as u32);
+            ((background * (255 - intensity) + foreground * intensity) / 255) as u8
+        };
+
+        [
+            mix(self.background[0], self.foreground[0]),
+            mix(self.background[1], self.foreground[1]),
+            mix(self.background[2], self.foreground[2]),
+            255,
+        ]
+    }
+}
+
+impl Default for Palette {
+    /// White on black.
+    fn default() -> Self {
+        Self::new([0x00, 0x00, 0x00], [0xFF, 0xFF, 0xFF])
+    }
+}
+
+/// Parses a `#RRGGBB` (or `RRGGBB`) color.
+pub fn parse_color(text: &str) -> Result<Color, String> {
+    let hex = text.trim().trim_start_matches('#');
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("Invalid color '{text}', expected #RRGGBB."));
+    }
+
+    let channel = |index: usize| {
+        u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16)
+            .map_err(|_| format!("Invalid color '{text}', expected #RRGGBB."))
+    };
+
+    Ok([channel(0)?, channel(1)?, channel(2)?])
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Parses `BACKGROUND,FOREGROUND`, e.g. `#000000,#FFFFFF`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once(',') {
+            Some((background, foreground)) => Ok(Self::new(
+                parse_color(background)?,
+                parse_color(foreground)?,
+            )),
+            None => Err(format!(
+                "Invalid palette '{text}', expected two colors like #000000,#FFFFFF."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [r, g, b] = self.background;
+        write!(f, "#{r:02X}{g:02X}{b:02X},")?;
+
+        let [r, g, b] = self.foreground;
+        write!(f, "#{r:02X}{g:02X}{b:02X}")
+    }
+}
+
+impl TryFrom<String> for Palette {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<Palette> for String {
+    fn from(palette: Palette) -> Self {
+        palette.to_string()
+    }
+}
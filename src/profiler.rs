@@ -0,0 +1,221 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{instruction::Instruction, Emulator};
+
+/// How many lines the tables of the report show at most.
+const REPORT_LINES: usize = 20;
+
+/// A subroutine that hasn't returned yet.
+struct Call {
+    address: u16,
+    /// The instructions run before the call.
+    start: u64,
+}
+
+/// The instructions run by a subroutine, or by the code around the calls (the entry point).
+#[derive(Clone, Copy, Debug, Default)]
+struct Subroutine {
+    calls: u64,
+    /// In the subroutine itself.
+    own: u64,
+    /// In the subroutine and the ones it called.
+    total: u64,
+}
+
+/// Counts the instructions a program runs: per address, per instruction, and per subroutine (from `2NNN` to `00EE`),
+/// to find where it spends its time. Fed by `Scheduler::run_frame_traced`.
+pub struct Profiler {
+    entry: u16,
+    instructions: u64,
+
+    /// The instructions run at each address, and the last one found there.
+    addresses: Vec<u64>,
+    decoded: Vec<Instruction>,
+    syntaxes: HashMap<&'static str, u64>,
+
+    stack: Vec<Call>,
+    subroutines: HashMap<u16, Subroutine>,
+    /// How many times a subroutine (or the entry point) called another one.
+    calls: HashMap<(u16, u16), u64>,
+}
+
+impl Profiler {
+    /// A profile of the program starting at `entry`.
+    pub fn new(entry: u16) -> Self {
+        Self {
+            entry,
+            instructions: 0,
+
+            addresses: vec![0; 4096],
+            decoded: vec![Instruction::Unknown { opcode: 0 }; 4096],
+            syntaxes: HashMap::new(),
+
+            stack: Vec::new(),
+            subroutines: HashMap::new(),
+            calls: HashMap::new(),
+        }
+    }
+
+    /// Counts the instruction `emulator` is about to run.
+    pub fn record(&mut self, emulator: &Emulator) {
+        let pc = emulator.program_counter as usize % self.addresses.len();
+        let instruction = emulator.next_instruction();
+
+        self.instructions += 1;
+        self.addresses[pc] += 1;
+        self.decoded[pc] = instruction;
+        *self.syntaxes.entry(instruction.syntax()).or_default() += 1;
+
+        let current = self.current();
+        self.subroutines.entry(current).or_default().own += 1;
+
+        match instruction {
+            Instruction::Call { address } => {
+                self.subroutines.entry(address).or_default().calls += 1;
+                *self.calls.entry((current, address)).or_default() += 1;
+                self.stack.push(Call {
+                    address,
+                    start: self.instructions,
+                });
+            }
+            // A return without a call (the program jumped out of a subroutine) is ignored
+            Instruction::Return => {
+                if let Some(call) = self.stack.pop() {
+                    self.subroutines.entry(call.address).or_default().total +=
+                        self.instructions - call.start;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The subroutine running, or the entry point.
+    fn current(&self) -> u16 {
+        self.stack.last().map_or(self.entry, |call| call.address)
+    }
+
+    /// The hotspots, the instructions, the subroutines and who calls them, most run first.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let total = self.instructions.max(1);
+        let percent = |count: u64| count as f64 * 100.0 / total as f64;
+
+        let _ = writeln!(report, "{} instructions", self.instructions);
+
+        let _ = writeln!(
+            report,
+            "\nHotspots\n  Address  Instructions       %  Instruction"
+        );
+        let mut addresses: Vec<_> = (0..self.addresses.len())
+            .filter(|&address| self.addresses[address] > 0)
+            .collect();
+        addresses.sort_by_key(|&address| std::cmp::Reverse(self.addresses[address]));
+
+        for &address in addresses.iter().take(REPORT_LINES) {
+            let count = self.addresses[address];
+            let _ = writeln!(
+                report,
+                "  {address:03X}      {count:>12}  {:>5.1}%  {}",
+                percent(count),
+                self.decoded[address]
+            );
+        }
+
+        let _ = writeln!(
+            report,
+            "\nInstructions\n  Instruction          Count       %"
+        );
+        let mut syntaxes: Vec<_> = self.syntaxes.iter().collect();
+        syntaxes.sort_by_key(|(syntax, count)| (std::cmp::Reverse(**count), **syntax));
+
+        for (syntax, &count) in syntaxes {
+            let _ = writeln!(
+                report,
+                "  {syntax:<18} {count:>8}  {:>5.1}%",
+                percent(count)
+            );
+        }
+
+        // The subroutines that haven't returned yet ran until now
+        let mut subroutines = self.subroutines.clone();
+        subroutines.entry(self.entry).or_default().total = self.instructions;
+        for call in &self.stack {
+            subroutines.entry(call.address).or_default().total += self.instructions - call.start;
+        }
+
+        let _ = writeln!(
+            report,
+            "\nSubroutines (with the ones they call / by themselves)\n  Address     Calls         Total       %           Own       %"
+        );
+        let mut sorted: Vec<_> = subroutines.iter().collect();
+        sorted
+            .sort_by_key(|(address, subroutine)| (std::cmp::Reverse(subroutine.total), **address));
+
+        for (&address, subroutine) in sorted.into_iter().take(REPORT_LINES) {
+            let name = if address == self.entry {
+                format!("{address:03X} entry")
+            } else {
+                format!("{address:03X}")
+            };
+
+            let _ = writeln!(
+                report,
+                "  {name:<9} {:>7}  {:>12}  {:>5.1}%  {:>12}  {:>5.1}%",
+                subroutine.calls,
+                subroutine.total,
+                percent(subroutine.total),
+                subroutine.own,
+                percent(subroutine.own)
+            );
+        }
+
+        let _ = writeln!(report, "\nCalls\n  Caller  Callee       Calls");
+        let mut calls: Vec<_> = self.calls.iter().collect();
+        calls.sort_by_key(|(edge, count)| (std::cmp::Reverse(**count), **edge));
+
+        for (&(caller, callee), count) in calls.into_iter().take(REPORT_LINES) {
+            let _ = writeln!(report, "  {caller:03X}  -> {callee:03X}  {count:>10}");
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{quirks::Quirks, PROGRAM_START};
+
+    #[test]
+    fn nested_calls() {
+        let program = [
+            0x22, 0x06, // CALL #206
+            0x12, 0x02, // JP #202
+            0x00, 0x00, // padding
+            0x22, 0x0C, // 206: CALL #20C
+            0x00, 0xEE, // RET
+            0x00, 0x00, // padding
+            0x60, 0x01, // 20C: LD V0, 1
+            0x00, 0xEE, // RET
+        ];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+        let mut profiler = Profiler::new(PROGRAM_START);
+
+        for _ in 0..6 {
+            profiler.record(&emulator);
+            emulator.run().unwrap();
+        }
+
+        let counts = |address| {
+            let subroutine = profiler.subroutines[&address];
+            (subroutine.calls, subroutine.own, subroutine.total)
+        };
+        assert_eq!(profiler.instructions, 6);
+        assert_eq!(counts(0x200).1, 2);
+        assert_eq!(counts(0x206), (1, 2, 4));
+        assert_eq!(counts(0x20C), (1, 2, 2));
+        assert_eq!(profiler.calls[&(0x200, 0x206)], 1);
+        assert_eq!(profiler.calls[&(0x206, 0x20C)], 1);
+        assert!(profiler.stack.is_empty());
+    }
+}
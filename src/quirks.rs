@@ -0,0 +1,168 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Behaviours that differ between CHIP-8 interpreters and that programs may rely on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// Sprites that cross the right or bottom edge are clipped instead of wrapping around to the other side.
+    /// The starting coordinate always wraps.
+    pub clip_sprites: bool,
+
+    /// In hi-res mode, VF is set to the number of sprite rows that collided (or were clipped at the bottom)
+    /// instead of being a 0/1 flag. (SUPER-CHIP 1.1)
+    pub count_collided_rows: bool,
+
+    /// Drawing a sprite waits for the next vertical blank, limiting programs to 60 sprites per second. (COSMAC VIP)
+    pub display_wait: bool,
+
+    /// `BXNN` jumps to XNN plus VX (X being the first digit of the address) instead of NNN plus V0. (CHIP-48, SUPER-CHIP)
+    pub jump_with_vx: bool,
+}
+
+impl Quirks {
+    /// The original COSMAC VIP interpreter. The display wait halves the speed of the programs that draw a lot,
+    /// `vip,no-display-wait` runs them without it.
+    pub const fn vip() -> Self {
+        Self {
+            clip_sprites: true,
+            count_collided_rows: false,
+            display_wait: true,
+            jump_with_vx: false,
+        }
+    }
+
+    /// SUPER-CHIP 1.1 on the HP-48.
+    pub const fn schip() -> Self {
+        Self {
+            clip_sprites: true,
+            count_collided_rows: true,
+            display_wait: false,
+            jump_with_vx: true,
+        }
+    }
+}
+
+/// Sprites wrap around the edges, and nothing else: how rusty8 always behaved, for the programs that don't ask
+/// for the quirks of a platform (with `--quirks`, `--platform` or the database).
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            clip_sprites: false,
+            count_collided_rows: false,
+            display_wait: false,
+            jump_with_vx: false,
+        }
+    }
+}
+
+impl fmt::Display for Quirks {
+    /// The enabled quirks, e.g. `clip-sprites, display-wait`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let enabled: Vec<_> = [
+            (self.clip_sprites, "clip-sprites"),
+            (self.count_collided_rows, "count-collided-rows"),
+            (self.display_wait, "display-wait"),
+            (self.jump_with_vx, "jump-with-vx"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| *name)
+        .collect();
+
+        if enabled.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", enabled.join(", "))
+        }
+    }
+}
+
+/// A change to the quirks: a preset (`vip` or `schip`), or a single quirk turned on (`clip-sprites`) or off (`no-clip-sprites`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum QuirkChange {
+    Preset(Quirks),
+    ClipSprites(bool),
+    CountCollidedRows(bool),
+    DisplayWait(bool),
+    JumpWithVx(bool),
+}
+
+impl QuirkChange {
+    pub fn apply(&self, quirks: &mut Quirks) {
+        match *self {
+            QuirkChange::Preset(preset) => *quirks = preset,
+            QuirkChange::ClipSprites(enabled) => quirks.clip_sprites = enabled,
+            QuirkChange::CountCollidedRows(enabled) => quirks.count_collided_rows = enabled,
+            QuirkChange::DisplayWait(enabled) => quirks.display_wait = enabled,
+            QuirkChange::JumpWithVx(enabled) => quirks.jump_with_vx = enabled,
+        }
+    }
+
+    /// The changes that turn any quirks into `quirks`, one per quirk.
+    pub fn all(quirks: Quirks) -> [QuirkChange; 4] {
+        [
+            QuirkChange::ClipSprites(quirks.clip_sprites),
+            QuirkChange::CountCollidedRows(quirks.count_collided_rows),
+            QuirkChange::DisplayWait(quirks.display_wait),
+            QuirkChange::JumpWithVx(quirks.jump_with_vx),
+        ]
+    }
+}
+
+impl FromStr for QuirkChange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, enabled) = match s.trim().strip_prefix("no-") {
+            Some(name) => (name, false),
+            None => (s.trim(), true),
+        };
+
+        match (name, enabled) {
+            ("vip", true) => Ok(QuirkChange::Preset(Quirks::vip())),
+            ("schip", true) => Ok(QuirkChange::Preset(Quirks::schip())),
+            ("clip-sprites", _) => Ok(QuirkChange::ClipSprites(enabled)),
+            ("count-collided-rows", _) => Ok(QuirkChange::CountCollidedRows(enabled)),
+            ("display-wait", _) => Ok(QuirkChange::DisplayWait(enabled)),
+            ("jump-with-vx", _) => Ok(QuirkChange::JumpWithVx(enabled)),
+            _ => Err(format!(
+                "Unknown quirk '{s}', expected vip, schip, or [no-]clip-sprites, [no-]count-collided-rows, [no-]display-wait, [no-]jump-with-vx."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for QuirkChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, enabled) = match *self {
+            QuirkChange::Preset(preset) if preset == Quirks::schip() => ("schip", true),
+            QuirkChange::Preset(_) => ("vip", true),
+            QuirkChange::ClipSprites(enabled) => ("clip-sprites", enabled),
+            QuirkChange::CountCollidedRows(enabled) => ("count-collided-rows", enabled),
+            QuirkChange::DisplayWait(enabled) => ("display-wait", enabled),
+            QuirkChange::JumpWithVx(enabled) => ("jump-with-vx", enabled),
+        };
+
+        if enabled {
+            write!(f, "{name}")
+        } else {
+            write!(f, "no-{name}")
+        }
+    }
+}
+
+impl TryFrom<String> for QuirkChange {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<QuirkChange> for String {
+    fn from(change: QuirkChange) -> Self {
+        change.to_string()
+    }
+}
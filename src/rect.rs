@@ -0,0 +1,70 @@
+/// A rectangular region of the screen, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub const fn pixel(x: usize, y: usize) -> Self {
+        Self::new(x, y, 1, 1)
+    }
+
+    pub const fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    pub const fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    /// The smallest rectangle containing both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+
+        Rect::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+}
+
+/// Combines two (possibly empty) regions.
+pub fn union(a: Option<Rect>, b: Option<Rect>) -> Option<Rect> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(&b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_contains_both() {
+        let a = Rect::new(2, 3, 4, 5);
+        let b = Rect::new(10, 1, 2, 2);
+
+        assert_eq!(a.union(&b), Rect::new(2, 1, 10, 7));
+        assert_eq!(union(Some(a), Some(b)), Some(Rect::new(2, 1, 10, 7)));
+        assert_eq!(union(None, Some(b)), Some(b));
+        assert_eq!(union(Some(a), None), Some(a));
+        assert_eq!(union(None, None), None);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{filter::ScreenFilter, osd::Overlay, palette::Palette, rect::Rect};
+
+/// The colors of the on-screen display, the same with every palette.
+const OSD_TEXT: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+const OSD_BOX: [u8; 4] = [0x20, 0x20, 0x20, 0xFF];
+
+/// Where the emulated screen ends up inside the window: scaled by a whole number and centered,
+/// with black bars filling the rest so the aspect ratio is preserved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub scale: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// The largest integer scale of a `screen_width` x `screen_height` screen that fits in the window.
+    pub fn fit(
+        window_width: usize,
+        window_height: usize,
+        screen_width: usize,
+        screen_height: usize,
+    ) -> Self {
+        let scale = (window_width / screen_width)
+            .min(window_height / screen_height)
+            .max(1);
+
+        let width = screen_width * scale;
+        let height = screen_height * scale;
+
+        Self {
+            scale,
+            x: window_width.saturating_sub(width) / 2,
+            y: window_height.saturating_sub(height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+/// Fills an RGBA `frame_buffer` of `width` x `height` pixels with the filtered screen, in the colors of `palette`.
+/// Only the pixels covering `region` of the screen are updated, or the whole window (including the bars around the screen) if there's none.
+pub fn rasterize(
+    frame_buffer: &mut [u8],
+    width: usize,
+    height: usize,
+    screen: &ScreenFilter,
+    (screen_width, screen_height): (usize, usize),
+    region: Option<Rect>,
+    palette: &Palette,
+) {
+    let viewport = Viewport::fit(width, height, screen_width, screen_height);
+
+    let region = match region {
+        Some(region) => region,
+        None => {
+            for pixel in frame_buffer.chunks_exact_mut(4) {
+                pixel.copy_from_slice(&palette.color(0));
+            }
+
+            Rect::new(0, 0, screen_width, screen_height)
+        }
+    };
+
+    for screen_y in region.y..region.bottom().min(screen_height) {
+        let top = viewport.y + screen_y * viewport.scale;
+
+        for y in top..(top + viewport.scale).min(height) {
+            for screen_x in region.x..region.right().min(screen_width) {
+                let color = palette.color(screen.intensity(screen_x, screen_y));
+                let left = viewport.x + screen_x * viewport.scale;
+
+                for x in left..(left + viewport.scale).min(width) {
+                    let offset = (y * width + x) * 4;
+                    frame_buffer[offset..offset + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// Draws the lines of the on-screen display over the top left corner of an RGBA `frame_buffer` of `width` x `height` pixels,
+/// with a font that grows with the window. It's opaque, so drawing it again over itself changes nothing.
+pub fn draw_osd(frame_buffer: &mut [u8], width: usize, height: usize, lines: &[String]) {
+    let overlay = Overlay::new(lines, (height / 160).max(1));
+    let (osd_width, osd_height) = overlay.size();
+
+    for y in 0..osd_height.min(height) {
+        for x in 0..osd_width.min(width) {
+            let color = match overlay.pixel(x, y) {
+                Some(true) => OSD_TEXT,
+                Some(false) => OSD_BOX,
+                None => continue,
+            };
+
+            let offset = (y * width + x) * 4;
+            frame_buffer[offset..offset + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filter::Filter, SCHIP_HEIGHT, SCHIP_WIDTH};
+
+    #[test]
+    fn rasterize_only_updates_the_region() {
+        let mut screen = [[0u8; SCHIP_HEIGHT]; SCHIP_WIDTH];
+        screen[1][1] = 1;
+        screen[5][5] = 1;
+        let mut filter = ScreenFilter::new(Filter::None);
+        filter.update(&screen, Some(Rect::new(0, 0, SCHIP_WIDTH, SCHIP_HEIGHT)));
+
+        // A 64x32 screen at a scale of 1, with a frame buffer filled with a color of neither the palette
+        let mut frame_buffer = vec![0x80; 64 * 32 * 4];
+        let palette = Palette::default();
+        rasterize(
+            &mut frame_buffer,
+            64,
+            32,
+            &filter,
+            (64, 32),
+            Some(Rect::new(0, 0, 2, 2)),
+            &palette,
+        );
+
+        let pixel = |x: usize, y: usize| &frame_buffer[(y * 64 + x) * 4..(y * 64 + x) * 4 + 4];
+        assert_eq!(pixel(0, 0), palette.color(0));
+        assert_eq!(pixel(1, 1), palette.color(255));
+        assert_eq!(pixel(2, 2), [0x80; 4]);
+        assert_eq!(pixel(5, 5), [0x80; 4]);
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use crate::Emulator;
+
+/// How often the display refreshes (and the timers count down).
+pub const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Roughly the speed of the COSMAC VIP interpreter.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: u32 = 15;
+
+/// How many frames run in the time of one when fast forwarding.
+pub const FAST_FORWARD_SPEED: u32 = 4;
+
+/// When the emulator falls behind (e.g. the window was being dragged), skip ahead instead of running all the missed frames at once.
+const MAX_FRAMES_BEHIND: u32 = 4;
+
+/// Splits the execution into 60Hz frames: a fixed amount of instructions, followed by a vertical blank.
+pub struct Scheduler {
+    instructions_per_frame: u32,
+    next_frame: Duration,
+    fast_forward: bool,
+}
+
+impl Scheduler {
+    pub fn new(instructions_per_frame: u32) -> Self {
+        Self {
+            instructions_per_frame,
+            next_frame: Duration::ZERO,
+            fast_forward: false,
+        }
+    }
+
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: u32) {
+        self.instructions_per_frame = instructions_per_frame;
+    }
+
+    pub fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    /// Runs `FAST_FORWARD_SPEED` frames in the time of one, or goes back to the normal speed.
+    pub fn set_fast_forward(&mut self, fast_forward: bool) {
+        self.fast_forward = fast_forward;
+    }
+
+    pub fn fast_forward(&self) -> bool {
+        self.fast_forward
+    }
+
+    /// The number of frames that should be run to catch up with the clock.
+    pub fn frames_due(&mut self, now: Duration) -> u32 {
+        let mut frames = 0;
+
+        while self.next_frame <= now {
+            self.next_frame += FRAME_DURATION;
+            frames += 1;
+        }
+
+        if frames > MAX_FRAMES_BEHIND {
+            self.next_frame = now + FRAME_DURATION;
+            frames = MAX_FRAMES_BEHIND;
+        }
+
+        if self.fast_forward {
+            frames * FAST_FORWARD_SPEED
+        } else {
+            frames
+        }
+    }
+
+    /// How long until `frames_due` returns at least one frame.
+    pub fn time_until_next_frame(&self, now: Duration) -> Duration {
+        self.next_frame.saturating_sub(now)
+    }
+
+    /// Runs the instructions of a single frame, and returns how many were run.
+    /// A sprite draw waiting for the display (see `Quirks::display_wait`) ends the frame early.
+    /// Fails on an instruction the emulator doesn't support, see `Emulator::run`.
+    pub fn run_frame(&self, emulator: &mut Emulator) -> Result<u32, String> {
+        self.run_frame_traced(emulator, |_| {})
+    }
+
+    /// Like `run_frame`, calling `trace` before every instruction.
+    pub fn run_frame_traced(
+        &self,
+        emulator: &mut Emulator,
+        mut trace: impl FnMut(&Emulator),
+    ) -> Result<u32, String> {
+        let mut instructions = 0;
+
+        while instructions < self.instructions_per_frame {
+            trace(emulator);
+            emulator.run()?;
+            instructions += 1;
+
+            if emulator.waiting_for_vblank {
+                break;
+            }
+        }
+
+        emulator.vertical_blank();
+        Ok(instructions)
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, NativeCallContext, AST, INT};
+
+use crate::{quirks::Quirks, Emulator, SCHIP_HEIGHT, SCHIP_WIDTH};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// What makes a hook run, before an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Trigger {
+    ProgramCounter(u16),
+    /// The opcodes equal to `value` in the bits of `mask`.
+    Opcode {
+        mask: u16,
+        value: u16,
+    },
+}
+
+impl Trigger {
+    /// A pattern like `FX33` or `Dxyn`: the hex digits must match, the other characters match anything.
+    fn opcode(pattern: &str) -> ScriptResult<Self> {
+        if pattern.chars().count() != 4 {
+            return Err(format!(
+                "Invalid opcode pattern '{pattern}', expected 4 characters like FX33"
+            )
+            .into());
+        }
+
+        let (mask, value) =
+            pattern.chars().fold((0, 0), |(mask, value), character| {
+                match character.to_digit(16) {
+                    Some(digit) => (mask << 4 | 0xF, value << 4 | digit as u16),
+                    None => (mask << 4, value << 4),
+                }
+            });
+
+        Ok(Trigger::Opcode { mask, value })
+    }
+}
+
+/// The state the functions of the script share.
+struct State {
+    /// The emulator the script runs on, while it runs (see `Script::attach`).
+    emulator: Emulator,
+    instructions_per_frame: u32,
+    /// The frames run since the script started.
+    frames: INT,
+
+    hooks: Vec<(Trigger, FnPtr)>,
+    frame_hooks: Vec<FnPtr>,
+
+    /// What the script printed, when it isn't printed to the terminal.
+    output: Option<Vec<String>>,
+}
+
+/// A script in Rhai, to automate the emulator: bots, test scenarios, cheats...
+///
+/// Its functions read and write the registers (`reg(x)`, `set_reg(x, value)`, `i()`, `pc()`, `dt()`, `st()`, `sp()`
+/// and their `set_` versions) and the memory (`peek(address)`, `poke(address, value)`), press the keys of the
+/// keypad (`press(key)`, `release(key)`), run frames (`step()`, `step(frames)`, `frames()`), read the screen
+/// (`pixel(x, y)`, `width()`, `height()`, `screen()`) and `reset()` the program.
+///
+/// `on_pc(address, || ...)` and `on_opcode("FX33", |opcode| ...)` run a function before the instructions
+/// at an address or with an opcode, and `on_frame(|| ...)` after every frame.
+pub struct Script {
+    path: PathBuf,
+    engine: Engine,
+    ast: AST,
+    state: Rc<RefCell<State>>,
+}
+
+impl Script {
+    /// Compiles the script at `path`. With `collect_output`, what it prints is kept for `take_output`
+    /// instead of going to the terminal.
+    pub fn load(path: &Path, collect_output: bool) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read {}: {error}", path.display()))?;
+
+        let state = Rc::new(RefCell::new(State {
+            emulator: Emulator::new(&[], Quirks::default(), 0),
+            instructions_per_frame: 1,
+            frames: 0,
+
+            hooks: Vec::new(),
+            frame_hooks: Vec::new(),
+
+            output: collect_output.then(Vec::new),
+        }));
+
+        let mut engine = Engine::new();
+        register(&mut engine, &state);
+
+        if collect_output {
+            let shared = state.clone();
+            engine.on_print(move |text| {
+                if let Some(output) = &mut shared.borrow_mut().output {
+                    output.push(text.to_string());
+                }
+            });
+        }
+
+        let ast = engine
+            .compile(&source)
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+            state,
+        })
+    }
+
+    /// Runs the top level of the script on `emulator`: a whole scenario, or the setup of the hooks.
+    pub fn run(
+        &mut self,
+        emulator: &mut Emulator,
+        instructions_per_frame: u32,
+    ) -> Result<(), String> {
+        let (engine, ast) = (&self.engine, &self.ast);
+
+        self.attach(emulator, instructions_per_frame, || engine.run_ast(ast))
+    }
+
+    /// Runs a frame of `emulator` like `Scheduler::run_frame`, with the hooks of the script.
+    /// Returns how many instructions were run.
+    pub fn run_frame(
+        &mut self,
+        emulator: &mut Emulator,
+        instructions_per_frame: u32,
+    ) -> Result<u32, String> {
+        self.run_frame_traced(emulator, instructions_per_frame, |_| {})
+    }
+
+    /// Like `run_frame`, calling `trace` before every instruction, after the hooks.
+    pub fn run_frame_traced(
+        &mut self,
+        emulator: &mut Emulator,
+        instructions_per_frame: u32,
+        mut trace: impl FnMut(&Emulator),
+    ) -> Result<u32, String> {
+        let (engine, ast, state) = (&self.engine, &self.ast, &self.state);
+
+        self.attach(emulator, instructions_per_frame, || {
+            run_frame(
+                state,
+                |callback, arguments| callback.call::<Dynamic>(engine, ast, arguments).map(|_| ()),
+                &mut trace,
+            )
+        })
+    }
+
+    /// What the script printed since the last call, if it's collected.
+    pub fn take_output(&mut self) -> Vec<String> {
+        self.state
+            .borrow_mut()
+            .output
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Lends `emulator` to the functions of the script while `run` runs.
+    fn attach<T>(
+        &self,
+        emulator: &mut Emulator,
+        instructions_per_frame: u32,
+        run: impl FnOnce() -> ScriptResult<T>,
+    ) -> Result<T, String> {
+        {
+            let mut state = self.state.borrow_mut();
+            std::mem::swap(&mut state.emulator, emulator);
+            state.instructions_per_frame = instructions_per_frame.max(1);
+        }
+
+        let result = run();
+
+        std::mem::swap(&mut self.state.borrow_mut().emulator, emulator);
+        result.map_err(|error| format!("{}: {error}", self.path.display()))
+    }
+}
+
+/// Runs a frame, calling the hooks with `call`. The state isn't borrowed while they run,
+/// so that they can use the functions of the script.
+fn run_frame(
+    state: &RefCell<State>,
+    mut call: impl FnMut(&FnPtr, Vec<Dynamic>) -> ScriptResult<()>,
+    trace: &mut dyn FnMut(&Emulator),
+) -> ScriptResult<u32> {
+    let mut instructions = 0;
+
+    loop {
+        let (limit, hooks) = {
+            let state = state.borrow();
+            let memory = state.emulator.memory();
+            let pc = state.emulator.program_counter;
+            let opcode = u16::from_be_bytes([
+                memory[pc as usize % memory.len()],
+                memory[(pc as usize + 1) % memory.len()],
+            ]);
+
+            let hooks: Vec<_> = state
+                .hooks
+                .iter()
+                .filter_map(|(trigger, callback)| match *trigger {
+                    Trigger::ProgramCounter(address) if address == pc => {
+                        Some((callback.clone(), vec![]))
+                    }
+                    Trigger::Opcode { mask, value } if opcode & mask == value => {
+                        Some((callback.clone(), vec![Dynamic::from(opcode as INT)]))
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            (state.instructions_per_frame, hooks)
+        };
+
+        if instructions >= limit {
+            break;
+        }
+
+        for (callback, arguments) in hooks {
+            call(&callback, arguments)?;
+        }
+
+        let mut state = state.borrow_mut();
+        trace(&state.emulator);
+        state.emulator.run()?;
+        instructions += 1;
+
+        if state.emulator.waiting_for_vblank {
+            break;
+        }
+    }
+
+    let frame_hooks = {
+        let mut state = state.borrow_mut();
+        state.emulator.vertical_blank();
+        state.frames += 1;
+        state.frame_hooks.clone()
+    };
+
+    for callback in frame_hooks {
+        call(&callback, vec![])?;
+    }
+
+    Ok(instructions)
+}
+
+fn check(value: INT, range: std::ops::RangeInclusive<INT>, what: &str) -> ScriptResult<INT> {
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!(
+            "Invalid {what} {value}, expected {} to {}",
+            range.start(),
+            range.end()
+        )
+        .into())
+    }
+}
+
+/// Registers the functions of the script.
+fn register(engine: &mut Engine, state: &Rc<RefCell<State>>) {
+    let shared = state.clone();
+    engine.register_fn("reg", move |x: INT| -> ScriptResult<INT> {
+        let x = check(x, 0..=15, "register")?;
+        Ok(shared.borrow().emulator.registers[x as usize] as INT)
+    });
+
+    let shared = state.clone();
+    engine.register_fn("set_reg", move |x: INT, value: INT| -> ScriptResult<()> {
+        let x = check(x, 0..=15, "register")?;
+        shared.borrow_mut().emulator.registers[x as usize] = value as u8;
+        Ok(())
+    });
+
+    let shared = state.clone();
+    engine.register_fn("i", move || shared.borrow().emulator.address as INT);
+
+    let shared = state.clone();
+    engine.register_fn("set_i", move |value: INT| {
+        shared.borrow_mut().emulator.address = value as u16 & 0xFFF;
+    });
+
+    let shared = state.clone();
+    engine.register_fn("pc", move || {
+        shared.borrow().emulator.program_counter as INT
+    });
+
+    let shared = state.clone();
+    engine.register_fn("set_pc", move |value: INT| {
+        shared.borrow_mut().emulator.program_counter = value as u16 & 0xFFF;
+    });
+
+    let shared = state.clone();
+    engine.register_fn("sp", move || shared.borrow().emulator.stack_pointer as INT);
+
+    let shared = state.clone();
+    engine.register_fn("dt", move || shared.borrow().emulator.delay_timer as INT);
+
+    let shared = state.clone();
+    engine.register_fn("set_dt", move |value: INT| {
+        shared.borrow_mut().emulator.delay_timer = value as u8;
+    });
+
+    let shared = state.clone();
+    engine.register_fn("st", move || shared.borrow().emulator.sound_timer as INT);
+
+    let shared = state.clone();
+    engine.register_fn("set_st", move |value: INT| {
+        shared.borrow_mut().emulator.sound_timer = value as u8;
+    });
+
+    let shared = state.clone();
+    engine.register_fn("peek", move |address: INT| -> ScriptResult<INT> {
+        let address = check(address, 0..=0xFFF, "address")?;
+        Ok(shared.borrow().emulator.memory()[address as usize] as INT)
+    });
+
+    let shared = state.clone();
+    engine.register_fn(
+        "poke",
+        move |address: INT, value: INT| -> ScriptResult<()> {
+            let address = check(address, 0..=0xFFF, "address")?;
+            shared
+                .borrow_mut()
+                .emulator
+                .poke(address as u16, value as u8);
+            Ok(())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn("press", move |key: INT| -> ScriptResult<()> {
+        let key = check(key, 0..=15, "key")?;
+        shared.borrow_mut().emulator.key_down(key as u8);
+        Ok(())
+    });
+
+    let shared = state.clone();
+    engine.register_fn("release", move |key: INT| -> ScriptResult<()> {
+        let key = check(key, 0..=15, "key")?;
+        shared.borrow_mut().emulator.key_up(key as u8);
+        Ok(())
+    });
+
+    let shared = state.clone();
+    engine.register_fn(
+        "step",
+        move |context: NativeCallContext, frames: INT| -> ScriptResult<()> {
+            for _ in 0..frames {
+                run_frame(
+                    &shared,
+                    |callback, arguments| {
+                        callback
+                            .call_within_context::<Dynamic>(&context, arguments)
+                            .map(|_| ())
+                    },
+                    &mut |_| {},
+                )?;
+            }
+            Ok(())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn(
+        "step",
+        move |context: NativeCallContext| -> ScriptResult<()> {
+            run_frame(
+                &shared,
+                |callback, arguments| {
+                    callback
+                        .call_within_context::<Dynamic>(&context, arguments)
+                        .map(|_| ())
+                },
+                &mut |_| {},
+            )
+            .map(|_| ())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn("frames", move || shared.borrow().frames);
+
+    let shared = state.clone();
+    engine.register_fn("reset", move || shared.borrow_mut().emulator.reset());
+
+    let shared = state.clone();
+    engine.register_fn("width", move || {
+        shared.borrow().emulator.resolution().0 as INT
+    });
+
+    let shared = state.clone();
+    engine.register_fn("height", move || {
+        shared.borrow().emulator.resolution().1 as INT
+    });
+
+    let shared = state.clone();
+    engine.register_fn("pixel", move |x: INT, y: INT| -> ScriptResult<bool> {
+        let x = check(x, 0..=SCHIP_WIDTH as INT - 1, "x")?;
+        let y = check(y, 0..=SCHIP_HEIGHT as INT - 1, "y")?;
+        Ok(shared.borrow().emulator.screen()[x as usize][y as usize] == 1)
+    });
+
+    // The visible screen, a line per row, `#` for the lit pixels
+    let shared = state.clone();
+    engine.register_fn("screen", move || {
+        let state = shared.borrow();
+        let (width, height) = state.emulator.resolution();
+        let screen = state.emulator.screen();
+
+        (0..height)
+            .map(|y| {
+                let row: String = (0..width)
+                    .map(|x| if screen[x][y] == 1 { '#' } else { '.' })
+                    .collect();
+                row + "\n"
+            })
+            .collect::<String>()
+    });
+
+    let shared = state.clone();
+    engine.register_fn(
+        "on_pc",
+        move |address: INT, callback: FnPtr| -> ScriptResult<()> {
+            let address = check(address, 0..=0xFFF, "address")?;
+            let trigger = Trigger::ProgramCounter(address as u16);
+            shared.borrow_mut().hooks.push((trigger, callback));
+            Ok(())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn(
+        "on_opcode",
+        move |pattern: &str, callback: FnPtr| -> ScriptResult<()> {
+            let trigger = Trigger::opcode(pattern)?;
+            shared.borrow_mut().hooks.push((trigger, callback));
+            Ok(())
+        },
+    );
+
+    let shared = state.clone();
+    engine.register_fn("on_frame", move |callback: FnPtr| {
+        shared.borrow_mut().frame_hooks.push(callback);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hook_pokes_the_memory() {
+        let path = std::env::temp_dir().join("rusty8-hook-pokes-the-memory.rhai");
+        fs::write(&path, "on_pc(0x202, || poke(0x300, 7));").unwrap();
+        let mut script = Script::load(&path, true).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // `LD I, #300`, `LD V0, [I]` and a jump to itself
+        let program = [0xA3, 0x00, 0xF0, 0x65, 0x12, 0x04];
+        let mut emulator = Emulator::new(&program, Quirks::default(), 0);
+
+        script.run(&mut emulator, 3).unwrap();
+        assert_eq!(script.run_frame(&mut emulator, 3), Ok(3));
+        assert_eq!(emulator.memory()[0x300], 7);
+        assert_eq!(emulator.registers[0], 7);
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::Write;
+
+/// How many bytes a strip of `sheet` shows, the most a DXYN sprite can use.
+pub const STRIP_HEIGHT: usize = 16;
+
+/// The width of a sprite.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpriteWidth {
+    /// 8 pixels, one byte per row, like DXYN.
+    #[default]
+    Narrow,
+    /// 16 pixels, two bytes per row, like the 16x16 sprites of DXY0 in the SCHIP hi-res mode.
+    Wide,
+}
+
+impl SpriteWidth {
+    pub fn pixels(self) -> usize {
+        match self {
+            SpriteWidth::Narrow => 8,
+            SpriteWidth::Wide => 16,
+        }
+    }
+
+    pub fn bytes_per_row(self) -> usize {
+        self.pixels() / 8
+    }
+
+    /// The rows of a typical sprite: as many as a DXYN sprite can have, or 16 for a 16x16 sprite.
+    pub fn default_height(self) -> usize {
+        match self {
+            SpriteWidth::Narrow => 15,
+            SpriteWidth::Wide => 16,
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            SpriteWidth::Narrow => SpriteWidth::Wide,
+            SpriteWidth::Wide => SpriteWidth::Narrow,
+        }
+    }
+}
+
+/// The `height` rows of the sprite at `address`, wrapping around the memory like DXYN.
+/// The leftmost pixel of a row is its highest bit.
+pub fn rows(memory: &[u8], address: u16, height: usize, width: SpriteWidth) -> Vec<u16> {
+    let bytes_per_row = width.bytes_per_row();
+
+    (0..height)
+        .map(|row| {
+            (0..bytes_per_row).fold(0, |bits, byte| {
+                let offset = address as usize + row * bytes_per_row + byte;
+                bits << 8 | memory[offset % memory.len()] as u16
+            })
+        })
+        .collect()
+}
+
+/// Draws rows of `width` pixels with `▀`, `▄` and `█`, two rows per line.
+pub fn half_blocks(rows: &[u16], width: SpriteWidth) -> Vec<String> {
+    let pixels = width.pixels();
+    let on = |row: Option<&u16>, column: usize| {
+        row.is_some_and(|bits| bits >> (pixels - 1 - column) & 1 == 1)
+    };
+
+    rows.chunks(2)
+        .map(|pair| {
+            (0..pixels)
+                .map(
+                    |column| match (on(pair.first(), column), on(pair.get(1), column)) {
+                        (false, false) => ' ',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (true, true) => '█',
+                    },
+                )
+                .collect()
+        })
+        .collect()
+}
+
+/// The whole program as a sprite sheet, to spot its graphics: strips of `STRIP_HEIGHT` rows side by side,
+/// `strips` per band, each one under the address it starts at.
+pub fn sheet(program: &[u8], load_address: u16, width: SpriteWidth, strips: usize) -> String {
+    let strip_bytes = STRIP_HEIGHT * width.bytes_per_row();
+    let band_bytes = strip_bytes * strips.max(1);
+
+    let mut text = String::new();
+
+    for (band, bytes) in program.chunks(band_bytes).enumerate() {
+        let start = load_address as usize + band * band_bytes;
+
+        let strips: Vec<_> = bytes
+            .chunks(strip_bytes)
+            .map(|strip| {
+                // The memory after the end of the program reads as blank rows
+                let mut padded = strip.to_vec();
+                padded.resize(strip_bytes, 0);
+                half_blocks(&rows(&padded, 0, STRIP_HEIGHT, width), width)
+            })
+            .collect();
+
+        let addresses: String = (0..strips.len())
+            .map(|index| {
+                let address = format!("{:03X}", start + index * strip_bytes);
+                format!("{address:<columns$}", columns = width.pixels() + 1)
+            })
+            .collect();
+
+        if band > 0 {
+            text.push('\n');
+        }
+        let _ = writeln!(text, "{}", addresses.trim_end());
+
+        for line in 0..STRIP_HEIGHT / 2 {
+            let line: Vec<_> = strips.iter().map(|strip| strip[line].as_str()).collect();
+            let _ = writeln!(text, "{}", line.join("|").trim_end());
+        }
+    }
+
+    text
+}
@@ -0,0 +1,199 @@
+use std::{
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use crate::{
+    database::Database,
+    filter::{Filter, ScreenFilter},
+    osd::Osd,
+    palette::Palette,
+    render,
+    scheduler::Scheduler,
+    Emulator,
+};
+
+struct Instance {
+    emulator: Emulator,
+    scheduler: Scheduler,
+    screen_filter: ScreenFilter,
+    palette: Palette,
+    osd: Osd,
+    /// The time of the last call to `rusty8_run`.
+    now: Duration,
+    /// RGBA, one pixel per pixel of the screen.
+    frame_buffer: Vec<u8>,
+    /// The program ran into an instruction that isn't supported, and doesn't run anymore.
+    stopped: bool,
+}
+
+impl Instance {
+    /// Runs `frames` frames and redraws the frame buffer.
+    /// An unsupported instruction stops the program there, and shows the error over the screen.
+    fn run_frames(&mut self, frames: u32) {
+        let mut instructions = 0;
+
+        for _ in 0..frames {
+            if self.stopped {
+                break;
+            }
+
+            match self.scheduler.run_frame(&mut self.emulator) {
+                Ok(run) => instructions += run as u64,
+                Err(error) => {
+                    self.osd.message(error, self.now);
+                    self.stopped = true;
+                }
+            }
+
+            let dirty = self.emulator.take_dirty();
+            self.screen_filter.update(&self.emulator.screen, dirty);
+        }
+
+        self.osd.count(frames, instructions, self.now);
+        self.osd.update(self.now);
+
+        let (width, height) = self.emulator.resolution();
+        self.frame_buffer.resize(width * height * 4, 0);
+
+        render::rasterize(
+            &mut self.frame_buffer,
+            width,
+            height,
+            &self.screen_filter,
+            (width, height),
+            None,
+            &self.palette,
+        );
+        render::draw_osd(&mut self.frame_buffer, width, height, &self.osd.lines());
+    }
+}
+
+static ROM: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+static INSTANCE: Mutex<Option<Instance>> = Mutex::new(None);
+/// Parsed at the first load only, it doesn't change.
+static DATABASE: OnceLock<Database> = OnceLock::new();
+
+fn with_instance<T: Default>(f: impl FnOnce(&mut Instance) -> T) -> T {
+    let mut instance = INSTANCE
+        .lock()
+        .expect("Could not get a lock on the emulator");
+
+    instance.as_mut().map(f).unwrap_or_default()
+}
+
+/// Returns a buffer of `length` bytes for the host to copy the program into, before calling `rusty8_load`.
+#[no_mangle]
+pub extern "C" fn rusty8_rom_buffer(length: usize) -> *mut u8 {
+    let mut rom = ROM.lock().expect("Could not get a lock on the program");
+
+    rom.clear();
+    rom.resize(length, 0);
+    rom.as_mut_ptr()
+}
+
+/// Starts the program copied into the buffer returned by `rusty8_rom_buffer`, with its settings from the bundled database.
+/// It's loaded at `load_address`, or where the database says when it's 0. Returns false if the program doesn't fit in
+/// the memory after it, and keeps running the previous one.
+#[no_mangle]
+pub extern "C" fn rusty8_load(seed: u32, load_address: u32) -> bool {
+    let rom = ROM.lock().expect("Could not get a lock on the program");
+    let mut settings = DATABASE.get_or_init(Database::bundled).settings(&rom);
+
+    match load_address {
+        0 => {}
+        0x001..=0xFFF => settings.load_address = load_address as u16,
+        _ => return false,
+    }
+
+    if Emulator::check_size(&rom, settings.load_address).is_err() {
+        return false;
+    }
+
+    let mut instance = Instance {
+        emulator: Emulator::with_load_address(
+            &rom,
+            settings.load_address,
+            settings.quirks,
+            seed as u64,
+        ),
+        scheduler: Scheduler::new(settings.instructions_per_frame),
+        screen_filter: ScreenFilter::new(Filter::default()),
+        palette: settings.palette,
+        osd: Osd::new(),
+        now: Duration::ZERO,
+        frame_buffer: Vec::new(),
+        stopped: false,
+    };
+    instance.osd.set_quirks(settings.quirks);
+    instance.run_frames(0);
+
+    *INSTANCE
+        .lock()
+        .expect("Could not get a lock on the emulator") = Some(instance);
+    true
+}
+
+/// Runs a fixed amount of frames, regardless of the time.
+#[no_mangle]
+pub extern "C" fn rusty8_step(frames: u32) {
+    with_instance(|instance| instance.run_frames(frames));
+}
+
+/// Runs the frames that are due at `milliseconds` since the host started. Returns how many were run.
+#[no_mangle]
+pub extern "C" fn rusty8_run(milliseconds: f64) -> u32 {
+    with_instance(|instance| {
+        instance.now = Duration::from_secs_f64(milliseconds.max(0.0) / 1000.0);
+        let frames = instance.scheduler.frames_due(instance.now);
+
+        if frames > 0 {
+            instance.run_frames(frames);
+        }
+
+        frames
+    })
+}
+
+/// The screen as RGBA pixels, `rusty8_width` x `rusty8_height`. Valid until the next frame is run.
+#[no_mangle]
+pub extern "C" fn rusty8_framebuffer() -> *const u8 {
+    with_instance(|instance| Some(instance.frame_buffer.as_ptr())).unwrap_or(std::ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn rusty8_width() -> u32 {
+    with_instance(|instance| instance.emulator.resolution().0 as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn rusty8_height() -> u32 {
+    with_instance(|instance| instance.emulator.resolution().1 as u32)
+}
+
+/// A key of the hex keypad (0x0 to 0xF) was pressed.
+#[no_mangle]
+pub extern "C" fn rusty8_key_down(key: u8) {
+    with_instance(|instance| instance.emulator.key_down(key & 0x0F));
+}
+
+/// A key of the hex keypad (0x0 to 0xF) was released.
+#[no_mangle]
+pub extern "C" fn rusty8_key_up(key: u8) {
+    with_instance(|instance| instance.emulator.key_up(key & 0x0F));
+}
+
+/// Whether the sound timer is active.
+#[no_mangle]
+pub extern "C" fn rusty8_sound() -> bool {
+    with_instance(|instance| instance.emulator.sound_timer > 0)
+}
+
+/// Shows or hides the frames and instructions per second and the quirks, over the screen.
+#[no_mangle]
+pub extern "C" fn rusty8_toggle_info() {
+    with_instance(|instance| {
+        instance.osd.toggle_info();
+        instance.run_frames(0);
+    });
+}
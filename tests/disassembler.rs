@@ -0,0 +1,51 @@
+// Checks that the assembler reads back what the disassembler writes: assembling the listing of a program gives the
+// same bytes.
+
+use rusty8::{assembler, disassembler, instruction::Instruction, PROGRAM_START};
+
+/// The programs of `roms/tests`.
+const ROMS: [&[u8]; 4] = [
+    include_bytes!("../roms/tests/collision.ch8"),
+    include_bytes!("../roms/tests/clipping.ch8"),
+    include_bytes!("../roms/tests/hires_collision.ch8"),
+    include_bytes!("../roms/tests/jump.ch8"),
+];
+
+/// Assembles the listing of `rom`, without its address and opcode columns.
+fn round_trip(rom: &[u8]) -> Result<Vec<u8>, String> {
+    let listing = disassembler::listing(rom, PROGRAM_START);
+    let source: Vec<_> = listing
+        .lines()
+        .map(|line| &line["200  00E0  ".len()..])
+        .collect();
+
+    assembler::assemble(&source.join("\n"), PROGRAM_START)
+}
+
+#[test]
+fn every_opcode() {
+    for opcode in 0..=u16::MAX {
+        let mnemonic = Instruction::decode(opcode).to_string();
+
+        assert_eq!(
+            assembler::assemble(&mnemonic, PROGRAM_START),
+            Ok(opcode.to_be_bytes().to_vec()),
+            "{opcode:04X} {mnemonic}"
+        );
+    }
+}
+
+#[test]
+fn test_roms() {
+    for rom in ROMS {
+        assert_eq!(round_trip(rom).as_deref(), Ok(rom));
+    }
+}
+
+/// The last byte of a program of an odd length is listed as `DB`.
+#[test]
+fn odd_length() {
+    let rom = [0x00, 0xE0, 0x12, 0x00, 0xAB];
+
+    assert_eq!(round_trip(&rom).as_deref(), Ok(&rom[..]));
+}
@@ -0,0 +1,199 @@
+// Drives `frontend::run` with a frontend that plays a list of events, one batch per frame, and records what it
+// was asked to show.
+
+use std::{path::PathBuf, time::Duration};
+
+use rusty8::{
+    cheats::Cheats,
+    database::Settings,
+    filter::{Filter, ScreenFilter},
+    frontend::{self, Event, Frontend, Registers, Session},
+    palette::Palette,
+    rect::Rect,
+    scheduler::{Scheduler, FRAME_DURATION},
+    Emulator,
+};
+
+/// `ADD V0, 1`, `CLS` and a jump to the start: V0 counts the frames, which all change the screen.
+const COUNTER: [u8; 6] = [0x70, 0x01, 0x00, 0xE0, 0x12, 0x00];
+
+/// `LD V1, 2` and a jump to itself.
+const OTHER: [u8; 4] = [0x61, 0x02, 0x12, 0x02];
+
+/// The clock moves a frame forward at every wait, so the frames keep coming unless paused.
+struct ScriptedFrontend {
+    events: Vec<Vec<Event>>,
+    frames: u32,
+
+    /// The regions of the calls to `present`, and the clock (in frames) when they happened.
+    presents: Vec<(u32, Option<Rect>)>,
+    registers: Vec<Registers>,
+    palettes: Vec<Palette>,
+}
+
+impl ScriptedFrontend {
+    /// Plays the batches of `events`, then quits.
+    fn new(mut events: Vec<Vec<Event>>) -> Self {
+        events.push(vec![Event::Quit]);
+        events.reverse();
+
+        Self {
+            events,
+            frames: 0,
+
+            presents: Vec::new(),
+            registers: Vec::new(),
+            palettes: Vec::new(),
+        }
+    }
+}
+
+impl Frontend for ScriptedFrontend {
+    fn present(
+        &mut self,
+        _screen: &ScreenFilter,
+        _resolution: (usize, usize),
+        region: Option<Rect>,
+    ) {
+        self.presents.push((self.frames, region));
+    }
+
+    fn show_registers(&mut self, registers: &Registers) {
+        self.registers.push(*registers);
+    }
+
+    fn set_palette(&mut self, palette: Palette) {
+        self.palettes.push(palette);
+    }
+
+    fn poll_input(&mut self) -> Vec<Event> {
+        self.events.pop().unwrap_or_default()
+    }
+
+    fn play_audio(&mut self, _playing: bool) {}
+
+    fn time(&self) -> Duration {
+        FRAME_DURATION * self.frames
+    }
+
+    fn wait(&mut self, _timeout: Duration) {
+        self.frames += 1;
+    }
+}
+
+/// `OTHER` runs slower, in other colors.
+struct TestSession;
+
+impl TestSession {
+    fn other() -> Settings {
+        Settings {
+            title: Some(String::from("Other")),
+            instructions_per_frame: 7,
+            palette: Palette::new([0x10, 0x20, 0x30], [0xC0, 0xB0, 0xA0]),
+            ..Settings::default()
+        }
+    }
+}
+
+impl Session for TestSession {
+    fn settings(&self, program: &[u8]) -> Settings {
+        if program == OTHER {
+            Self::other()
+        } else {
+            Settings::default()
+        }
+    }
+
+    fn save_settings(&self, _settings: &Settings) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn capture_scale(&self) -> usize {
+        1
+    }
+
+    fn capture_directory(&self) -> PathBuf {
+        std::env::temp_dir()
+    }
+
+    fn cheats(&self, program: &[u8]) -> Result<Cheats, String> {
+        Ok(Cheats::new(program))
+    }
+
+    fn save_cheats(&self, _cheats: &Cheats) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Runs `program` with 3 instructions per frame through the batches of `events`.
+fn run(program: &[u8], events: Vec<Vec<Event>>) -> (ScriptedFrontend, Emulator, Scheduler) {
+    let mut frontend = ScriptedFrontend::new(events);
+    let mut emulator = Emulator::new(program, Settings::default().quirks, 0);
+    let mut scheduler = Scheduler::new(3);
+    let mut screen_filter = ScreenFilter::new(Filter::None);
+
+    frontend::run(
+        &mut frontend,
+        &mut emulator,
+        &mut scheduler,
+        &mut screen_filter,
+        Settings::default(),
+        &TestSession,
+    )
+    .unwrap();
+
+    (frontend, emulator, scheduler)
+}
+
+#[test]
+fn frames_follow_the_clock() {
+    let (frontend, emulator, _) = run(&COUNTER, vec![vec![], vec![], vec![]]);
+
+    // A frame per batch of events, and none with the one that quits
+    assert_eq!(Registers::of(&emulator).v[0], 3);
+    assert_eq!(frontend.registers.len(), 3);
+}
+
+#[test]
+fn step_runs_a_single_frame_while_paused() {
+    let events = vec![
+        vec![Event::TogglePause],
+        vec![],
+        vec![Event::Step],
+        vec![],
+        vec![],
+    ];
+    let (frontend, emulator, _) = run(&COUNTER, events);
+
+    assert_eq!(Registers::of(&emulator).v[0], 1);
+    assert_eq!(frontend.registers.len(), 1);
+    assert_eq!(frontend.registers[0].v[0], 1);
+
+    // The screen is drawn when the emulator starts, and again only after the step
+    let after_start: Vec<_> = frontend
+        .presents
+        .iter()
+        .filter(|(frame, _)| *frame > 0)
+        .collect();
+    assert_eq!(after_start.len(), 1);
+    assert_eq!(after_start[0].0, 2);
+}
+
+#[test]
+fn load_switches_the_settings() {
+    let (frontend, emulator, scheduler) = run(&COUNTER, vec![vec![Event::Load(OTHER.to_vec())]]);
+
+    assert_eq!(emulator.program(), OTHER);
+    assert_eq!(Registers::of(&emulator).v[1], 2);
+    assert_eq!(scheduler.instructions_per_frame(), 7);
+    assert_eq!(frontend.palettes, [TestSession::other().palette]);
+}
+
+#[test]
+fn load_keeps_the_running_program_when_too_large() {
+    let (frontend, emulator, scheduler) = run(&COUNTER, vec![vec![Event::Load(vec![0; 4000])]]);
+
+    assert_eq!(emulator.program(), COUNTER);
+    assert_eq!(scheduler.instructions_per_frame(), 3);
+    assert!(frontend.palettes.is_empty());
+}
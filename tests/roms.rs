@@ -0,0 +1,73 @@
+// Runs the programs of `roms/tests` without a frontend, and checks the results they store in the registers
+// from VA (see `roms/tests/README.md`).
+
+use rusty8::{
+    frontend::Registers,
+    quirks::Quirks,
+    scheduler::{Scheduler, DEFAULT_INSTRUCTIONS_PER_FRAME},
+    Emulator,
+};
+
+/// Long enough for every test to draw its results, even waiting for the display before each sprite.
+const FRAMES: u32 = 120;
+
+/// The results of the tests of `rom`, in VA, VB...
+fn results(rom: &[u8], quirks: Quirks, tests: usize) -> Vec<u8> {
+    let mut emulator = Emulator::new(rom, quirks, 0);
+    let scheduler = Scheduler::new(DEFAULT_INSTRUCTIONS_PER_FRAME);
+
+    for _ in 0..FRAMES {
+        scheduler.run_frame(&mut emulator).unwrap();
+    }
+
+    Registers::of(&emulator).v[0xA..0xA + tests].to_vec()
+}
+
+#[test]
+fn collision() {
+    let rom = include_bytes!("../roms/tests/collision.ch8");
+
+    for quirks in [Quirks::default(), Quirks::vip(), Quirks::schip()] {
+        assert_eq!(results(rom, quirks, 5), [1, 1, 0, 0, 1], "{quirks}");
+    }
+}
+
+#[test]
+fn clipping() {
+    let rom = include_bytes!("../roms/tests/clipping.ch8");
+    let wrapping = Quirks {
+        clip_sprites: false,
+        ..Quirks::vip()
+    };
+
+    assert_eq!(results(rom, Quirks::vip(), 3), [0, 0, 1]);
+    assert_eq!(results(rom, wrapping, 3), [1, 1, 1]);
+}
+
+#[test]
+fn hires_collision() {
+    let rom = include_bytes!("../roms/tests/hires_collision.ch8");
+
+    assert_eq!(results(rom, Quirks::schip(), 4), [2, 2, 4, 1]);
+    assert_eq!(results(rom, Quirks::vip(), 4), [1, 0, 1, 1]);
+}
+
+#[test]
+fn jump() {
+    let rom = include_bytes!("../roms/tests/jump.ch8");
+    let jump_with_vx = Quirks {
+        jump_with_vx: true,
+        ..Quirks::default()
+    };
+
+    assert_eq!(results(rom, Quirks::default(), 2), [1, 2]);
+    assert_eq!(results(rom, jump_with_vx, 2), [2, 1]);
+}
+
+/// Without quirks, the sprites wrap around the edges like rusty8 always did.
+#[test]
+fn default_wraps_sprites() {
+    let rom = include_bytes!("../roms/tests/clipping.ch8");
+
+    assert_eq!(results(rom, Quirks::default(), 3), [1, 1, 1]);
+}
@@ -0,0 +1,80 @@
+// Thin wrapper around the exports of the WebAssembly build (see `src/wasm.rs`).
+
+/** Maps the keyboard to the hex keypad, like the desktop frontends do. */
+export const KEYPAD = {
+    '1': 0x1, '2': 0x2, '3': 0x3, '4': 0xC,
+    'q': 0x4, 'w': 0x5, 'e': 0x6, 'r': 0xD,
+    'a': 0x7, 's': 0x8, 'd': 0x9, 'f': 0xE,
+    'z': 0xA, 'x': 0x0, 'c': 0xB, 'v': 0xF,
+};
+
+export class Rusty8 {
+    /** Instantiates the module from the bytes (or a `fetch` response) of `rusty8.wasm`. */
+    static async load(source) {
+        const { instance } = source instanceof Response
+            ? await WebAssembly.instantiateStreaming(source, {})
+            : await WebAssembly.instantiate(source, {});
+
+        return new Rusty8(instance.exports);
+    }
+
+    constructor(exports) {
+        this.exports = exports;
+    }
+
+    /**
+     * Starts a program, given as a `Uint8Array`, at `loadAddress` (where the database says by default).
+     * Throws if it doesn't fit in the memory, and the previous program keeps running.
+     */
+    start(program, seed = Math.floor(Math.random() * 0xFFFFFFFF), loadAddress = 0) {
+        const pointer = this.exports.rusty8_rom_buffer(program.length);
+        new Uint8Array(this.exports.memory.buffer, pointer, program.length).set(program);
+
+        if (!this.exports.rusty8_load(seed, loadAddress)) {
+            throw new Error(`The program (${program.length} bytes) doesn't fit in the memory after its load address`);
+        }
+    }
+
+    /** Runs a fixed amount of frames. */
+    step(frames = 1) {
+        this.exports.rusty8_step(frames);
+    }
+
+    /** Runs the frames that are due at `milliseconds` (e.g. from `performance.now()`). Returns how many were run. */
+    run(milliseconds) {
+        return this.exports.rusty8_run(milliseconds);
+    }
+
+    get width() {
+        return this.exports.rusty8_width();
+    }
+
+    get height() {
+        return this.exports.rusty8_height();
+    }
+
+    /** The screen as RGBA pixels, copied so it stays valid after the next frame. */
+    framebuffer() {
+        const length = this.width * this.height * 4;
+
+        return new Uint8ClampedArray(this.exports.memory.buffer, this.exports.rusty8_framebuffer(), length).slice();
+    }
+
+    keyDown(key) {
+        this.exports.rusty8_key_down(key);
+    }
+
+    keyUp(key) {
+        this.exports.rusty8_key_up(key);
+    }
+
+    /** Shows or hides the frames and instructions per second and the quirks, drawn into the frame buffer. */
+    toggleInfo() {
+        this.exports.rusty8_toggle_info();
+    }
+
+    /** Whether the sound timer is active. */
+    get sound() {
+        return this.exports.rusty8_sound() !== 0;
+    }
+}
@@ -0,0 +1,98 @@
+// Run with `npm test` in web/, which builds the WebAssembly module first, or with `node --test web/rusty8.test.mjs`
+// after building it (see web/README.md).
+import { test } from 'node:test';
+import assert from 'node:assert/strict';
+import { readFile } from 'node:fs/promises';
+
+import { Rusty8 } from './rusty8.js';
+
+const root = new URL('../', import.meta.url);
+const wasm = process.env.RUSTY8_WASM
+    ?? new URL('target/wasm32-unknown-unknown/release/rusty8.wasm', root);
+
+async function emulator() {
+    try {
+        return await Rusty8.load(await readFile(wasm));
+    } catch (error) {
+        throw new Error(`Could not load ${wasm}, build it first: ${error.message}`);
+    }
+}
+
+/** The top left corner of the screen, `#` for pixels that are on. */
+function ascii(emulator, width, height) {
+    const pixels = emulator.framebuffer();
+    const lines = [];
+
+    for (let y = 0; y < height; y++) {
+        let line = '';
+        for (let x = 0; x < width; x++) {
+            line += pixels[(y * emulator.width + x) * 4] > 0 ? '#' : '.';
+        }
+        lines.push(line);
+    }
+
+    return lines.join('\n');
+}
+
+test('runs the collision test ROM', async () => {
+    const rusty8 = await emulator();
+    rusty8.start(await readFile(new URL('roms/tests/collision.ch8', root)));
+    rusty8.step(200);
+
+    assert.equal(rusty8.width, 64);
+    assert.equal(rusty8.height, 32);
+    assert.equal(ascii(rusty8, 32, 10), [
+        '................................',
+        '................................',
+        '................................',
+        '................................',
+        '......#.....#...####..####....#.',
+        '.....##....##...#..#..#..#...##.',
+        '......#.....#...#..#..#..#....#.',
+        '......#.....#...#..#..#..#....#.',
+        '.....###...###..####..####...###',
+        '................................',
+    ].join('\n'));
+});
+
+test('waits for a key to be released', async () => {
+    const rusty8 = await emulator();
+    // LD V0, K / LD F, V0 / LD V1, 0 / DRW V1, V1, 5 / JP 0x208
+    rusty8.start(new Uint8Array([0xF0, 0x0A, 0xF0, 0x29, 0x61, 0x00, 0xD1, 0x15, 0x12, 0x08]));
+
+    rusty8.keyDown(0x5);
+    rusty8.step(10);
+    assert.equal(ascii(rusty8, 4, 5), ['....', '....', '....', '....', '....'].join('\n'));
+
+    rusty8.keyUp(0x5);
+    rusty8.step(10);
+    assert.equal(ascii(rusty8, 4, 5), ['####', '#...', '####', '...#', '####'].join('\n'));
+});
+
+test('switches to the SCHIP resolution', async () => {
+    const rusty8 = await emulator();
+    // HIGH / JP 0x202
+    rusty8.start(new Uint8Array([0x00, 0xFF, 0x12, 0x02]));
+    rusty8.step(1);
+
+    assert.equal(rusty8.width, 128);
+    assert.equal(rusty8.height, 64);
+    assert.equal(rusty8.framebuffer().length, 128 * 64 * 4);
+});
+
+test('loads at another address', async () => {
+    const rusty8 = await emulator();
+    // At 0x600: LD I, 0x606 / DRW V1, V1, 5 / JP 0x604, and the sprite of an 8 at 0x606
+    rusty8.start(new Uint8Array([0xA6, 0x06, 0xD1, 0x15, 0x16, 0x04, 0xF0, 0x90, 0xF0, 0x90, 0xF0]), 0, 0x600);
+    rusty8.step(1);
+
+    assert.equal(ascii(rusty8, 4, 5), ['####', '#..#', '####', '#..#', '####'].join('\n'));
+});
+
+test('refuses a program too large for the memory', async () => {
+    const rusty8 = await emulator();
+
+    assert.throws(() => rusty8.start(new Uint8Array(0x1000 - 0x200 + 1)), /doesn't fit/);
+    assert.throws(() => rusty8.start(new Uint8Array(0x201), 0, 0xE00), /doesn't fit/);
+    rusty8.start(new Uint8Array(0x200), 0, 0xE00);
+});